@@ -1,12 +1,20 @@
 use axum::{
+    extract::{OriginalUri, Path},
     http::{HeaderMap, Request, StatusCode},
     middleware::Next,
     response::IntoResponse,
     body::Body,
+    Extension,
     Json
 };
+use chrono::Utc;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use crate::models::{LoginInfo, LoginResponse, Claims};
+use sqlx::PgPool;
+use uuid::Uuid;
+use crate::{
+    models::{LoginInfo, LoginResponse, Claims},
+    verification::{verify_operator_request, FreshnessWindow, OperatorRequest, PgNonceStore, VerificationError},
+};
 
 pub async fn login_handler(Json(login_info): Json<LoginInfo>) -> Result<Json<LoginResponse>, StatusCode> {
     let username = &login_info.username;
@@ -65,4 +73,93 @@ pub async fn auth_middleware(req: Request<Body>, next: Next) -> impl IntoRespons
         Ok(_) => next.run(req).await,
         Err(status) => status.into_response(),
     }
-}
\ No newline at end of file
+}
+
+/// Guards `/api/admin` routes with the shared `ADMIN_TOKEN`.
+pub async fn admin_middleware(req: Request<Body>, next: Next) -> impl IntoResponse {
+    let admin_token = match config::get_config().admin_token {
+        Some(token) => token,
+        None => return StatusCode::FORBIDDEN.into_response(),
+    };
+
+    let provided = req
+        .headers()
+        .get("X-Admin-Token")
+        .and_then(|value| value.to_str().ok());
+    if provided == Some(admin_token.as_str()) {
+        next.run(req).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+/// Guards a validator's operator routes with a signature by the validator's
+/// registered key over the request, see `operator_request_payload`. The
+/// signature is sent base58 in `X-Validator-Signature`, the time it was
+/// made, in unix milliseconds, in `X-Validator-Timestamp`, and for methods
+/// other than `GET` the signed nonce in `X-Validator-Nonce`.
+pub async fn validator_auth_middleware(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+    req: Request<Body>,
+    next: Next,
+) -> impl IntoResponse {
+    let headers = req.headers();
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(signature), Some(timestamp)) = (
+        header("X-Validator-Signature").map(str::to_string),
+        header("X-Validator-Timestamp").and_then(|timestamp| timestamp.parse::<i64>().ok()),
+    ) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    let nonce = header("X-Validator-Nonce").map(str::to_string);
+
+    let public_key = match sqlx::query_scalar::<_, String>("SELECT public_key FROM validators WHERE id = $1")
+        .bind(validator_id)
+        .fetch_optional(&pool)
+        .await
+    {
+        Ok(Some(public_key)) => public_key,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            println!("Error fetching validator: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // Signed as sent, before nesting strips the `/api` prefix
+    let uri = req.extensions().get::<OriginalUri>().map(|uri| &uri.0).unwrap_or(req.uri());
+    let path_and_query = uri.path_and_query().map(|path| path.as_str()).unwrap_or(uri.path());
+    let window = FreshnessWindow::from_config(&config::get_config());
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            println!("Error acquiring connection: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let request = OperatorRequest {
+        validator_id,
+        method: req.method().as_str(),
+        path_and_query,
+        timestamp,
+        nonce: nonce.as_deref(),
+        signature: &signature,
+    };
+    let verified =
+        verify_operator_request(request, &public_key, Utc::now(), window, &mut PgNonceStore(&mut conn)).await;
+    drop(conn);
+    match verified {
+        Ok(()) => next.run(req).await,
+        Err(VerificationError::Stale | VerificationError::FromTheFuture) => {
+            (StatusCode::UNAUTHORIZED, "Stale signature").into_response()
+        }
+        Err(VerificationError::MalformedNonce) => (StatusCode::UNAUTHORIZED, "Missing or malformed nonce").into_response(),
+        Err(VerificationError::Replayed) => (StatusCode::UNAUTHORIZED, "Replayed request").into_response(),
+        Err(VerificationError::Database(e)) => {
+            println!("Error claiming nonce: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(_) => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
@@ -0,0 +1,755 @@
+use std::{collections::HashMap, str::FromStr};
+
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use chrono::Utc;
+use config::get_config;
+use serde_json::json;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction as SolanaTransaction, TransactionError},
+};
+use solana_client::rpc_client::RpcClient;
+use sqlx::{PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{Payout, PayoutBatchCreate, PayoutBatchReport},
+    payouts::{
+        confirm_payout, fail_payout, payout_amount, payouts_paused, rpc_client, treasury_keypair, PAYOUT_COLUMNS,
+    },
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+/// Fee of a transaction with one signature, as every batch transaction has.
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// A payout of a batch and where its transfer goes.
+struct Recipient {
+    payout_id: Uuid,
+    destination: Pubkey,
+    amount: u64,
+}
+
+fn transfers(recipients: &[Recipient], payer: &Pubkey) -> Vec<Instruction> {
+    recipients
+        .iter()
+        .map(|recipient| system_instruction::transfer(payer, &recipient.destination, recipient.amount))
+        .collect()
+}
+
+/// Bytes of the signed transaction paying `recipients`: its one signature,
+/// prefixed by the signature count, and the message.
+fn transaction_size(recipients: &[Recipient], payer: &Pubkey) -> usize {
+    1 + 64 + Message::new(&transfers(recipients, payer), Some(payer)).serialize().len()
+}
+
+/// Transfers to distinct destinations that fit one transaction.
+fn transfers_per_transaction() -> usize {
+    let payer = Pubkey::new_unique();
+    let mut recipients = Vec::new();
+    loop {
+        recipients.push(Recipient {
+            payout_id: Uuid::nil(),
+            destination: Pubkey::new_unique(),
+            amount: 0,
+        });
+        if transaction_size(&recipients, &payer) > PACKET_DATA_SIZE {
+            return recipients.len() - 1;
+        }
+    }
+}
+
+/// Splits recipients, in order, into as few transactions as fit a packet.
+fn pack(recipients: Vec<Recipient>, payer: &Pubkey) -> Vec<Vec<Recipient>> {
+    let mut transactions = Vec::new();
+    let mut current: Vec<Recipient> = Vec::new();
+    for recipient in recipients {
+        current.push(recipient);
+        if current.len() > 1 && transaction_size(&current, payer) > PACKET_DATA_SIZE {
+            let overflow = current.pop().unwrap();
+            transactions.push(std::mem::replace(&mut current, vec![overflow]));
+        }
+    }
+    if !current.is_empty() {
+        transactions.push(current);
+    }
+    transactions
+}
+
+/// A validator owed at least a batch's threshold.
+pub(crate) struct Candidate {
+    pub validator_id: Uuid,
+    pub public_key: String,
+    pub credits: i32,
+    /// Lamports it would be paid.
+    pub amount: i64,
+}
+
+/// Who a batch would pay: validators owed at least the threshold, largest
+/// payouts first, while they and their fees fit the spending cap. The rest
+/// are deferred to a later batch.
+pub(crate) struct BatchPlan {
+    pub recipients: Vec<Candidate>,
+    pub deferred: Vec<Candidate>,
+}
+
+impl BatchPlan {
+    pub fn amount(&self) -> i64 {
+        self.recipients.iter().map(|candidate| candidate.amount).sum()
+    }
+}
+
+enum Claim {
+    Paused,
+    Claimed {
+        batch: Option<(Uuid, Vec<(Payout, String)>)>,
+        deferred: i32,
+    },
+}
+
+/// The outcome of a batch payout.
+pub enum BatchRun {
+    /// Payouts are paused; nothing was claimed.
+    Paused,
+    /// `batch` is `None` when nobody was paid. `deferred` validators were
+    /// owed the threshold but left for a later batch by the spending cap.
+    Ran {
+        batch: Option<PayoutBatchReport>,
+        deferred: i32,
+    },
+}
+
+/// A batch payout that stopped on a database error, with the batch it had
+/// claimed, if any.
+#[derive(Debug)]
+pub struct BatchError {
+    pub batch_id: Option<Uuid>,
+    pub error: sqlx::Error,
+}
+
+impl From<sqlx::Error> for BatchError {
+    fn from(error: sqlx::Error) -> Self {
+        BatchError { batch_id: None, error }
+    }
+}
+
+/// Plans a batch over the validators that aren't banned and have no payout
+/// in flight. With `lock` their rows are locked for the transaction,
+/// skipping validators another batch is claiming.
+async fn plan_batch(
+    conn: &mut PgConnection,
+    threshold: i32,
+    spend_cap: Option<i64>,
+    lock: bool,
+) -> Result<BatchPlan, sqlx::Error> {
+    // A payout is at most its credits, so this only drops validators that can't qualify
+    let validators = sqlx::query(&format!(
+        "SELECT v.id, v.public_key, v.pending_payouts, v.reputation
+         FROM validators v
+         WHERE v.status <> 'banned' AND v.pending_payouts > 0 AND v.pending_payouts >= $1
+           AND NOT EXISTS (
+               SELECT 1 FROM payouts p WHERE p.validator_id = v.id AND p.status IN ('created', 'submitted')
+           )
+         ORDER BY v.id
+         {}",
+        if lock { "FOR UPDATE OF v SKIP LOCKED" } else { "" }
+    ))
+    .bind(threshold)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let candidates = validators
+        .iter()
+        .map(|validator| {
+            let credits = validator.get::<i32, _>("pending_payouts");
+            Candidate {
+                validator_id: validator.get("id"),
+                public_key: validator.get("public_key"),
+                credits,
+                amount: payout_amount(credits, validator.get::<f64, _>("reputation")),
+            }
+        })
+        .collect();
+    Ok(select_recipients(candidates, threshold, spend_cap))
+}
+
+/// Drops candidates whose payout is under the threshold, which reputation
+/// weighting can make it even when their credits aren't, and takes the rest
+/// largest first while they and the fees of their transactions fit the
+/// spending cap. A candidate over what is left of the cap is deferred and
+/// smaller ones after it may still fit. A payout over the whole cap would
+/// never fit, so it is paid alone in a batch of its own.
+fn select_recipients(candidates: Vec<Candidate>, threshold: i32, spend_cap: Option<i64>) -> BatchPlan {
+    let mut candidates: Vec<Candidate> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.amount > 0 && candidate.amount >= threshold as i64)
+        .collect();
+    candidates.sort_by(|a, b| b.amount.cmp(&a.amount).then(a.validator_id.cmp(&b.validator_id)));
+
+    let mut plan = BatchPlan {
+        recipients: Vec::new(),
+        deferred: Vec::new(),
+    };
+    let per_transaction = transfers_per_transaction();
+    let spend_cap = spend_cap.unwrap_or(i64::MAX);
+    let mut budget = spend_cap;
+    for candidate in candidates {
+        // Recipients are packed in this order, so every `per_transaction`th opens a transaction
+        let fee = if plan.recipients.len().is_multiple_of(per_transaction) {
+            LAMPORTS_PER_SIGNATURE as i64
+        } else {
+            0
+        };
+        let cost = candidate.amount.saturating_add(fee);
+        if cost <= budget {
+            budget -= cost;
+            plan.recipients.push(candidate);
+        } else if plan.recipients.is_empty() && cost > spend_cap {
+            budget = 0;
+            plan.recipients.push(candidate);
+        } else {
+            plan.deferred.push(candidate);
+        }
+    }
+    plan
+}
+
+/// What a batch would pay right now, without claiming anything.
+pub(crate) async fn estimate_batch(
+    pool: &PgPool,
+    threshold: i32,
+    spend_cap: Option<i64>,
+) -> Result<BatchPlan, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    plan_batch(&mut conn, threshold, spend_cap, false).await
+}
+
+/// Moves the pending payouts of the validators a batch pays into `created`
+/// payouts of a new batch. Returns the batch and its payouts with each
+/// validator's public key, or `None` when nobody is paid, along with the
+/// number of validators deferred by the spending cap.
+async fn claim_batch(
+    pool: &PgPool,
+    threshold: i32,
+    spend_cap: Option<i64>,
+) -> Result<Claim, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if payouts_paused(&mut tx).await? {
+        return Ok(Claim::Paused);
+    }
+    let plan = plan_batch(&mut tx, threshold, spend_cap, true).await?;
+    let deferred = plan.deferred.len() as i32;
+    if plan.recipients.is_empty() {
+        return Ok(Claim::Claimed { batch: None, deferred });
+    }
+
+    let mut public_keys = HashMap::new();
+    let (mut validator_ids, mut credits, mut amounts) = (Vec::new(), Vec::new(), Vec::new());
+    for candidate in plan.recipients {
+        validator_ids.push(candidate.validator_id);
+        credits.push(candidate.credits);
+        amounts.push(candidate.amount);
+        public_keys.insert(candidate.validator_id, candidate.public_key);
+    }
+
+    let now = Utc::now().naive_utc();
+    let batch_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO payout_batches (threshold, spend_cap, created_at) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(threshold)
+    .bind(spend_cap)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+    let payouts = sqlx::query_as::<_, Payout>(&format!(
+        "INSERT INTO payouts (validator_id, batch_id, credits, amount, status, created_at)
+         SELECT validator_id, $1, credits, amount, 'created', $5
+         FROM UNNEST($2::UUID[], $3::INTEGER[], $4::BIGINT[]) AS claimed(validator_id, credits, amount)
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(batch_id)
+    .bind(&validator_ids)
+    .bind(&credits)
+    .bind(&amounts)
+    .bind(now)
+    .fetch_all(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE validators v SET pending_payouts = v.pending_payouts - claimed.credits
+         FROM UNNEST($1::UUID[], $2::INTEGER[]) AS claimed(validator_id, credits)
+         WHERE v.id = claimed.validator_id",
+    )
+    .bind(&validator_ids)
+    .bind(&credits)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let payouts = payouts
+        .into_iter()
+        .map(|payout| {
+            let public_key = public_keys.remove(&payout.validator_id).unwrap_or_default();
+            (payout, public_key)
+        })
+        .collect();
+    Ok(Claim::Claimed {
+        batch: Some((batch_id, payouts)),
+        deferred,
+    })
+}
+
+async fn fail_all(pool: &PgPool, recipients: &[Recipient], error: &str) -> Result<(), sqlx::Error> {
+    for recipient in recipients {
+        fail_payout(pool, recipient.payout_id, error).await?;
+    }
+    Ok(())
+}
+
+/// Signs one transaction paying `recipients`, records its signature on
+/// their payouts and only then sends it. Recipients whose payouts were
+/// failed in the meantime, e.g. by the reconciler, are left out. A transfer
+/// the transaction fails on fails its own payout, and the others are sent
+/// again without it. Returns the lamports the treasury spends if the
+/// transaction lands.
+async fn submit_transaction(
+    pool: &PgPool,
+    client: &RpcClient,
+    keypair: &Keypair,
+    mut recipients: Vec<Recipient>,
+) -> Result<u64, sqlx::Error> {
+    loop {
+        if recipients.is_empty() {
+            return Ok(0);
+        }
+        let (recent_blockhash, last_valid_block_height) = match client.get_latest_blockhash_with_commitment(client.commitment()) {
+            Ok(latest) => latest,
+            Err(e) => {
+                fail_all(pool, &recipients, &format!("Failed to get blockhash: {}", e)).await?;
+                return Ok(0);
+            }
+        };
+        let transaction = SolanaTransaction::new_signed_with_payer(
+            &transfers(&recipients, &keypair.pubkey()),
+            Some(&keypair.pubkey()),
+            &[keypair],
+            recent_blockhash,
+        );
+        let signature = transaction.signatures[0];
+        let payout_ids: Vec<Uuid> = recipients.iter().map(|recipient| recipient.payout_id).collect();
+
+        let mut tx = pool.begin().await?;
+        let submitted = sqlx::query_scalar::<_, Uuid>(
+            "UPDATE payouts SET status = 'submitted', signature = $2, recent_blockhash = $3,
+                                last_valid_block_height = $4, submitted_at = $5
+             WHERE id = ANY($1) AND status = 'created'
+             RETURNING id",
+        )
+        .bind(&payout_ids)
+        .bind(signature.to_string())
+        .bind(recent_blockhash.to_string())
+        .bind(last_valid_block_height as i64)
+        .bind(Utc::now().naive_utc())
+        .fetch_all(&mut *tx)
+        .await?;
+        if submitted.len() < recipients.len() {
+            // Every transfer in the transaction must belong to a payout it submits
+            tx.rollback().await?;
+            recipients.retain(|recipient| submitted.contains(&recipient.payout_id));
+            continue;
+        }
+        tx.commit().await?;
+
+        let spent = recipients.iter().map(|recipient| recipient.amount).sum::<u64>() + LAMPORTS_PER_SIGNATURE;
+        match client.send_and_confirm_transaction(&transaction) {
+            Ok(_) => {
+                println!("Batch transfer {} paid {} validators", signature, recipients.len());
+                for payout_id in payout_ids {
+                    confirm_payout(pool, payout_id).await?;
+                }
+            }
+            Err(e) => match e.get_transaction_error() {
+                // The transaction was rejected, or landed failed, as a whole
+                // because of one transfer: none of them moved any lamports
+                Some(TransactionError::InstructionError(index, error)) if (index as usize) < recipients.len() => {
+                    let failed = recipients.remove(index as usize);
+                    println!("Batch transfer {} failed on payout {}: {}", signature, failed.payout_id, error);
+                    fail_payout(pool, failed.payout_id, &format!("Transfer failed: {}", error)).await?;
+                    let retried = sqlx::query_scalar::<_, Uuid>(
+                        "UPDATE payouts SET status = 'created', signature = NULL, recent_blockhash = NULL,
+                                            last_valid_block_height = NULL, submitted_at = NULL
+                         WHERE id = ANY($1) AND status = 'submitted' AND signature = $2
+                         RETURNING id",
+                    )
+                    .bind(&payout_ids)
+                    .bind(signature.to_string())
+                    .fetch_all(pool)
+                    .await?;
+                    recipients.retain(|recipient| retried.contains(&recipient.payout_id));
+                    continue;
+                }
+                _ => println!("Batch transfer {} not confirmed yet: {}", signature, e),
+            },
+        }
+        return Ok(spent);
+    }
+}
+
+/// The least a batch may pay a validator: the rent-exempt minimum of an
+/// account without data, below which a transfer to a new account fails.
+pub(crate) fn minimum_threshold() -> Result<u64, String> {
+    rpc_client()?
+        .get_minimum_balance_for_rent_exemption(0)
+        .map_err(|e| format!("Failed to fetch rent-exempt minimum: {}", e))
+}
+
+/// Reports of the given batches, newest first.
+pub async fn batch_reports(pool: &PgPool, batch_ids: &[Uuid]) -> Result<Vec<PayoutBatchReport>, sqlx::Error> {
+    sqlx::query_as::<_, PayoutBatchReport>(
+        "SELECT b.id, b.threshold, b.spend_cap, b.created_at, b.completed_at,
+                COUNT(p.id) AS recipients,
+                COUNT(DISTINCT p.signature) AS transactions,
+                COUNT(p.id) FILTER (WHERE p.status = 'confirmed') AS confirmed,
+                COUNT(p.id) FILTER (WHERE p.status IN ('created', 'submitted')) AS in_flight,
+                COUNT(p.id) FILTER (WHERE p.status = 'failed') AS failed,
+                COALESCE(SUM(p.amount), 0)::BIGINT AS amount,
+                COALESCE(SUM(p.amount) FILTER (WHERE p.status = 'confirmed'), 0)::BIGINT AS paid_out
+         FROM payout_batches b
+         LEFT JOIN payouts p ON p.batch_id = b.id
+         WHERE b.id = ANY($1)
+         GROUP BY b.id
+         ORDER BY b.created_at DESC",
+    )
+    .bind(batch_ids)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn batch_report(pool: &PgPool, batch_id: Uuid) -> Result<Option<PayoutBatchReport>, sqlx::Error> {
+    Ok(batch_reports(pool, &[batch_id]).await?.pop())
+}
+
+/// Pays every validator owed at least `threshold` lamports, up to
+/// `spend_cap` lamports in all with fees, with as few transactions as
+/// possible. Each
+/// transaction lands or fails as a whole, so payouts are resolved per
+/// transaction: a batch can end with some transactions confirmed, some
+/// failed and their credits given back, and some left `submitted` for the
+/// reconciler. Pausing payouts fails the transactions not sent yet.
+pub async fn run_batch_payout(
+    pool: &PgPool,
+    threshold: i32,
+    spend_cap: Option<i64>,
+) -> Result<BatchRun, BatchError> {
+    let (batch_id, claimed, deferred) = match claim_batch(pool, threshold, spend_cap).await? {
+        Claim::Paused => return Ok(BatchRun::Paused),
+        Claim::Claimed { batch: None, deferred } => return Ok(BatchRun::Ran { batch: None, deferred }),
+        Claim::Claimed {
+            batch: Some((batch_id, claimed)),
+            deferred,
+        } => (batch_id, claimed, deferred),
+    };
+
+    let batch = pay_batch(pool, batch_id, claimed).await.map_err(|error| BatchError {
+        batch_id: Some(batch_id),
+        error,
+    })?;
+    Ok(BatchRun::Ran { batch, deferred })
+}
+
+/// Sends the transfers of a claimed batch and returns its report.
+async fn pay_batch(
+    pool: &PgPool,
+    batch_id: Uuid,
+    claimed: Vec<(Payout, String)>,
+) -> Result<Option<PayoutBatchReport>, sqlx::Error> {
+    let mut recipients = Vec::with_capacity(claimed.len());
+    for (payout, public_key) in claimed {
+        match Pubkey::from_str(&public_key) {
+            Ok(destination) => recipients.push(Recipient {
+                payout_id: payout.id,
+                destination,
+                amount: payout.amount as u64,
+            }),
+            Err(e) => fail_payout(pool, payout.id, &format!("Invalid destination pubkey: {}", e)).await?,
+        }
+    }
+
+    let treasury = rpc_client().and_then(|client| {
+        let keypair = treasury_keypair()?;
+        let balance = client
+            .get_balance(&keypair.pubkey())
+            .map_err(|e| format!("Failed to fetch balance: {}", e))?;
+        Ok((client, keypair, balance))
+    });
+    match treasury {
+        Ok((client, keypair, mut available)) => {
+            let mut paused = false;
+            for transaction in pack(recipients, &keypair.pubkey()) {
+                // The kill switch also stops a batch between transactions
+                paused = paused || payouts_paused(&mut *pool.acquire().await?).await?;
+                if paused {
+                    fail_all(pool, &transaction, "Payouts were paused").await?;
+                    continue;
+                }
+                let needed = transaction.iter().map(|recipient| recipient.amount).sum::<u64>() + LAMPORTS_PER_SIGNATURE;
+                if needed > available {
+                    fail_all(pool, &transaction, "Insufficient balance").await?;
+                    continue;
+                }
+                available -= submit_transaction(pool, &client, &keypair, transaction).await?;
+            }
+        }
+        Err(e) => fail_all(pool, &recipients, &e).await?,
+    }
+
+    sqlx::query("UPDATE payout_batches SET completed_at = $2 WHERE id = $1")
+        .bind(batch_id)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await?;
+    batch_report(pool, batch_id).await
+}
+
+/// `POST /api/admin/payouts/batch`: runs a batch payout now.
+pub(crate) async fn create_payout_batch_handler(
+    Extension(pool): Extension<PgPool>,
+    payload: Option<Json<PayoutBatchCreate>>,
+) -> ApiJsonResponse {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let threshold = payload.threshold.unwrap_or_else(|| get_config().payout_batch_threshold);
+    match minimum_threshold() {
+        Ok(minimum) if (threshold as i64) < minimum as i64 => {
+            return ApiJsonResponse(
+                StatusCode::BAD_REQUEST,
+                json_error(&format!("threshold must be at least the rent-exempt minimum of {} lamports", minimum)),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => {
+            println!("Error fetching rent-exempt minimum: {}", e);
+            return ApiJsonResponse(
+                StatusCode::BAD_GATEWAY,
+                json_error("Failed to fetch rent-exempt minimum"),
+            );
+        }
+    }
+    if payload.spend_cap.is_some_and(|spend_cap| spend_cap <= 0) {
+        return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("spend_cap must be positive"));
+    }
+
+    match run_batch_payout(&pool, threshold, payload.spend_cap).await {
+        Ok(BatchRun::Paused) => ApiJsonResponse(StatusCode::SERVICE_UNAVAILABLE, json_error("Payouts are paused")),
+        Ok(BatchRun::Ran { batch, deferred }) => {
+            let message = if batch.is_some() {
+                "Batch payout sent"
+            } else {
+                "No validator is owed the threshold within the spending cap"
+            };
+            ApiJsonResponse(
+                StatusCode::OK,
+                json_success(json!({"batch": batch, "deferred": deferred}), Some(message.to_string())),
+            )
+        }
+        Err(e) => {
+            println!("Error running batch payout: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to run batch payout"),
+            )
+        }
+    }
+}
+
+/// `GET /api/admin/payouts/batches/:id`: a batch with each of its payouts.
+pub(crate) async fn get_payout_batch_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(batch_id): Path<Uuid>,
+) -> ApiJsonResponse {
+    let batch = match batch_report(&pool, batch_id).await {
+        Ok(Some(batch)) => batch,
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Batch not found")),
+        Err(e) => {
+            println!("Error fetching payout batch: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch payout batch"),
+            );
+        }
+    };
+
+    let payouts = sqlx::query_as::<_, Payout>(&format!(
+        "SELECT {} FROM payouts WHERE batch_id = $1 ORDER BY signature NULLS LAST, amount DESC, id",
+        PAYOUT_COLUMNS
+    ))
+    .bind(batch_id)
+    .fetch_all(&pool)
+    .await;
+
+    match payouts {
+        Ok(payouts) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(json!({"batch": batch, "payouts": payouts}), None),
+        ),
+        Err(e) => {
+            println!("Error fetching payout batch: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch payout batch"),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipients(count: usize) -> Vec<Recipient> {
+        (0..count)
+            .map(|_| Recipient {
+                payout_id: Uuid::new_v4(),
+                destination: Pubkey::new_unique(),
+                amount: 1_000_000,
+            })
+            .collect()
+    }
+
+    fn candidate(id: u128, credits: i32, amount: i64) -> Candidate {
+        Candidate {
+            validator_id: Uuid::from_u128(id),
+            public_key: Pubkey::new_unique().to_string(),
+            credits,
+            amount,
+        }
+    }
+
+    fn ids(candidates: &[Candidate]) -> Vec<u128> {
+        candidates.iter().map(|candidate| candidate.validator_id.as_u128()).collect()
+    }
+
+    #[test]
+    fn packs_a_full_packet_into_one_transaction() {
+        let payer = Pubkey::new_unique();
+        let capacity = transfers_per_transaction();
+        assert!(capacity > 1);
+
+        let transactions = pack(recipients(capacity), &payer);
+        assert_eq!(transactions.len(), 1);
+        assert!(transaction_size(&transactions[0], &payer) <= PACKET_DATA_SIZE);
+    }
+
+    #[test]
+    fn starts_a_new_transaction_past_the_packet_size() {
+        let payer = Pubkey::new_unique();
+        let capacity = transfers_per_transaction();
+        let sent = recipients(capacity * 2 + 1);
+        let order = sent.iter().map(|recipient| recipient.payout_id).collect::<Vec<_>>();
+
+        let transactions = pack(sent, &payer);
+        assert_eq!(
+            transactions.iter().map(Vec::len).collect::<Vec<_>>(),
+            [capacity, capacity, 1]
+        );
+        for transaction in &transactions {
+            assert!(transaction_size(transaction, &payer) <= PACKET_DATA_SIZE);
+        }
+        // Recipients keep their order across transactions
+        let packed = transactions.iter().flatten().map(|recipient| recipient.payout_id).collect::<Vec<_>>();
+        assert_eq!(packed, order);
+    }
+
+    #[test]
+    fn packs_nothing_into_no_transaction() {
+        assert!(pack(Vec::new(), &Pubkey::new_unique()).is_empty());
+    }
+
+    #[test]
+    fn selects_largest_payouts_first() {
+        let plan = select_recipients(
+            vec![candidate(1, 100, 100), candidate(2, 300, 300), candidate(3, 200, 200), candidate(4, 200, 200)],
+            0,
+            None,
+        );
+        assert_eq!(ids(&plan.recipients), [2, 3, 4, 1]);
+        assert!(plan.deferred.is_empty());
+        assert_eq!(plan.amount(), 800);
+    }
+
+    #[test]
+    fn defers_a_payout_over_the_cap_and_keeps_filling_it() {
+        let plan = select_recipients(
+            vec![
+                candidate(1, 500_000, 500_000),
+                candidate(2, 400_000, 400_000),
+                candidate(3, 300_000, 300_000),
+                candidate(4, 50_000, 50_000),
+            ],
+            0,
+            Some(850_000 + LAMPORTS_PER_SIGNATURE as i64),
+        );
+        // 400,000 doesn't fit after 500,000, but 300,000 and 50,000 do
+        assert_eq!(ids(&plan.recipients), [1, 3, 4]);
+        assert_eq!(ids(&plan.deferred), [2]);
+        assert_eq!(plan.amount(), 850_000);
+    }
+
+    #[test]
+    fn counts_transaction_fees_in_the_cap() {
+        let plan = select_recipients(
+            vec![candidate(1, 600_000, 600_000), candidate(2, 400_000, 400_000)],
+            0,
+            Some(1_000_000),
+        );
+        assert_eq!(ids(&plan.recipients), [1]);
+        assert_eq!(ids(&plan.deferred), [2]);
+
+        // One more recipient than a transaction holds opens a second one with its own fee
+        let per_transaction = transfers_per_transaction();
+        let candidates = || (0..=per_transaction as u128).map(|id| candidate(id, 1_000, 1_000)).collect();
+        let transfers = (per_transaction as i64 + 1) * 1_000;
+        let fee = LAMPORTS_PER_SIGNATURE as i64;
+        let plan = select_recipients(candidates(), 0, Some(transfers + fee));
+        assert_eq!(plan.recipients.len(), per_transaction);
+        assert_eq!(plan.deferred.len(), 1);
+        let plan = select_recipients(candidates(), 0, Some(transfers + 2 * fee));
+        assert_eq!(plan.recipients.len(), per_transaction + 1);
+    }
+
+    #[test]
+    fn pays_a_payout_over_the_whole_cap_alone() {
+        let plan = select_recipients(
+            vec![candidate(1, 3_000_000, 3_000_000), candidate(2, 2_000_000, 2_000_000), candidate(3, 500_000, 500_000)],
+            0,
+            Some(1_000_000),
+        );
+        assert_eq!(ids(&plan.recipients), [1]);
+        assert_eq!(ids(&plan.deferred), [2, 3]);
+
+        // The next batch pays the next one
+        let plan = select_recipients(
+            vec![candidate(2, 2_000_000, 2_000_000), candidate(3, 500_000, 500_000)],
+            0,
+            Some(1_000_000),
+        );
+        assert_eq!(ids(&plan.recipients), [2]);
+        assert_eq!(ids(&plan.deferred), [3]);
+    }
+
+    #[test]
+    fn drops_payouts_weighted_under_the_threshold() {
+        // Enough credits, but reputation weighting leaves the payout under the threshold
+        let plan = select_recipients(
+            vec![candidate(1, 1000, 400), candidate(2, 600, 600), candidate(3, 500, 0)],
+            500,
+            None,
+        );
+        assert_eq!(ids(&plan.recipients), [2]);
+        assert!(plan.deferred.is_empty());
+    }
+}
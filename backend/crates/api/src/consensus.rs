@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Timelike, Utc};
+use config::{get_config, Config};
+use monitor_core::consensus::{decide, ConsensusConfig, QuorumRule, Vote};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::models::StatusRound;
+
+const CONSENSUS_INTERVAL_SECS: u64 = 15;
+const CONSENSUS_WATERMARK: &str = "consensus_rounds";
+/// Validators report a round within this long after it ends, or after a
+/// planned round was assigned; later ticks are not counted.
+const ROUND_SETTLE_SECS: i64 = 30;
+/// Upper bound on the rounds one pass decides, and on how far back the
+/// first pass starts.
+const MAX_MINUTES_PER_PASS: i64 = 60;
+const INITIAL_LOOKBACK_HOURS: i64 = 24;
+/// Rounds returned with the status of a single website.
+const RECENT_ROUNDS: i64 = 60;
+
+/// The minute of `at`. Ticks outside a planned round, from heartbeats or
+/// from before rounds were planned, vote in the minute they were recorded.
+pub fn round_start(at: NaiveDateTime) -> NaiveDateTime {
+    at.with_second(0).and_then(|at| at.with_nanosecond(0)).unwrap_or(at)
+}
+
+/// Joins each tick `wt` to the round it answered, when the scheduler planned
+/// one for its `round_id`. Replies to a round arrive over the validators'
+/// response timeout and may straddle a minute, so a planned round starts
+/// when it was assigned, see `TICK_ROUND_START`.
+pub(crate) const TICK_ROUND_JOIN: &str = "LEFT JOIN LATERAL (
+         SELECT MIN(ca.assigned_at) AS assigned_at
+         FROM check_assignments ca
+         WHERE ca.round_id = wt.round_id AND ca.website_id = wt.website_id
+     ) planned ON true";
+
+/// The start of the round a tick votes in: its planned round's, or the
+/// minute it was recorded in.
+pub(crate) const TICK_ROUND_START: &str = "COALESCE(planned.assigned_at, date_trunc('minute', wt.created_at))";
+
+/// Reads the quorum settings from the config, falling back to a simple
+/// majority when the rule does not parse.
+pub fn consensus_config() -> ConsensusConfig {
+    let config = get_config();
+    let rule = config.consensus_rule.parse::<QuorumRule>().unwrap_or_else(|e| {
+        println!("Invalid CONSENSUS_RULE, using majority: {}", e);
+        QuorumRule::Majority
+    });
+    ConsensusConfig {
+        rule,
+        min_locations: config.consensus_min_locations,
+        reputation_weighting: config.consensus_reputation_weighting,
+    }
+}
+
+/// Rejects an n-of-m rule that needs more votes than the validators each
+/// website is sent to, which would leave every round inconclusive.
+pub fn validate_consensus_rule(config: &Config) -> Result<(), String> {
+    match config.consensus_rule.parse::<QuorumRule>() {
+        Ok(QuorumRule::NOfM { m, .. }) if m > config.validators_per_check => Err(format!(
+            "CONSENSUS_RULE {} needs {} validators per check, but VALIDATORS_PER_CHECK is {}",
+            config.consensus_rule, m, config.validators_per_check
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Decides every round starting in `[from, to)` and stores the verdicts in
+/// `website_status_rounds`. Rounds are redecided when run again. Planned
+/// rounds are keyed by their `round_id`, so replies in different minutes
+/// count together and two rounds in one minute stay apart. Each validator
+/// votes once per round with its latest tick; ticks recorded by the backend
+/// itself always weigh 1.
+pub async fn decide_rounds(
+    pool: &PgPool,
+    config: &ConsensusConfig,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<usize, sqlx::Error> {
+    // Ticks come in after their round starts, up to the settle time
+    let rows = sqlx::query(&format!(
+        "SELECT wt.website_id, {start} AS round_start,
+                CASE WHEN planned.assigned_at IS NOT NULL THEN wt.round_id END AS round_id,
+                wt.validator_id, v.location, v.reputation, wt.status, wt.latency, wt.error_class,
+                w.monitor_type = 'heartbeat' AS heartbeat
+         FROM website_ticks wt
+         JOIN websites w ON w.id = wt.website_id
+         LEFT JOIN validators v ON v.id = wt.validator_id
+         {join}
+         WHERE wt.created_at >= $1 AND wt.created_at < $3
+           AND {start} >= $1 AND {start} < $2
+         ORDER BY wt.created_at",
+        start = TICK_ROUND_START,
+        join = TICK_ROUND_JOIN,
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(to + ChronoDuration::seconds(ROUND_SETTLE_SECS))
+    .fetch_all(pool)
+    .await?;
+
+    let mut rounds: BTreeMap<(Uuid, NaiveDateTime, Option<Uuid>), BTreeMap<Option<Uuid>, Vote>> = BTreeMap::new();
+    let mut heartbeats = BTreeSet::new();
+    for row in &rows {
+        if row.get::<bool, _>("heartbeat") {
+            heartbeats.insert(row.get::<Uuid, _>("website_id"));
+        }
+        let validator_id = row.get::<Option<Uuid>, _>("validator_id");
+        let status = row.get::<String, _>("status");
+        let up = status == "Good";
+        let vote = Vote {
+            validator_id,
+            location: row
+                .get::<Option<String>, _>("location")
+                .filter(|location| !location.is_empty() && location != "unknown"),
+            up,
+            latency: row.get::<f64, _>("latency"),
+            // Failures without an error class, e.g. from heartbeats, fall back to the status
+            reason: (!up).then(|| row.get::<Option<String>, _>("error_class").unwrap_or(status)),
+            weight: match row.get::<Option<f64>, _>("reputation") {
+                Some(reputation) if config.reputation_weighting => reputation,
+                _ => 1.0,
+            },
+        };
+        rounds
+            .entry((row.get("website_id"), row.get("round_start"), row.get("round_id")))
+            .or_default()
+            .insert(validator_id, vote);
+    }
+
+    let mut tx = pool.begin().await?;
+    for ((website_id, round_start, _), votes) in &rounds {
+        let votes = votes.values().cloned().collect::<Vec<_>>();
+        let verdict = if heartbeats.contains(website_id) {
+            decide(&votes, &config.for_heartbeats())
+        } else {
+            decide(&votes, config)
+        };
+        sqlx::query(
+            "INSERT INTO website_status_rounds
+                 (website_id, round_start, status, up_votes, down_votes, locations, latency,
+                  failing_validators, error_reasons, decided_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (website_id, round_start) DO UPDATE SET
+                 status = EXCLUDED.status,
+                 up_votes = EXCLUDED.up_votes,
+                 down_votes = EXCLUDED.down_votes,
+                 locations = EXCLUDED.locations,
+                 latency = EXCLUDED.latency,
+                 failing_validators = EXCLUDED.failing_validators,
+                 error_reasons = EXCLUDED.error_reasons,
+                 decided_at = EXCLUDED.decided_at",
+        )
+        .bind(website_id)
+        .bind(round_start)
+        .bind(verdict.status.as_str())
+        .bind(verdict.up_votes as i32)
+        .bind(verdict.down_votes as i32)
+        .bind(verdict.locations as i32)
+        .bind(verdict.latency)
+        .bind(&verdict.failing_validators)
+        .bind(&verdict.error_reasons)
+        .bind(Utc::now().naive_utc())
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(rounds.len())
+}
+
+/// The latest decided rounds of a website, newest first.
+pub async fn recent_rounds(pool: &PgPool, website_id: Uuid) -> Result<Vec<StatusRound>, sqlx::Error> {
+    sqlx::query_as::<_, StatusRound>(
+        "SELECT round_start, status, up_votes, down_votes, locations, latency,
+                failing_validators, error_reasons
+         FROM website_status_rounds
+         WHERE website_id = $1
+         ORDER BY round_start DESC
+         LIMIT $2",
+    )
+    .bind(website_id)
+    .bind(RECENT_ROUNDS)
+    .fetch_all(pool)
+    .await
+}
+
+/// Status of the latest conclusive round, `unknown` without one.
+pub fn current_status(rounds: &[StatusRound]) -> &str {
+    rounds
+        .iter()
+        .find(|round| round.status != "inconclusive")
+        .map_or("unknown", |round| round.status.as_str())
+}
+
+/// Start of the first round that has not been decided yet.
+pub async fn consensus_watermark(pool: &PgPool) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    let row = sqlx::query("SELECT watermark FROM job_watermarks WHERE name = $1")
+        .bind(CONSENSUS_WATERMARK)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| row.get::<NaiveDateTime, _>("watermark")))
+}
+
+async fn decide_pending(pool: &PgPool, config: &ConsensusConfig) -> Result<usize, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let settled = round_start(now - ChronoDuration::seconds(ROUND_SETTLE_SECS));
+    let from = match consensus_watermark(pool).await? {
+        Some(watermark) => watermark,
+        None => round_start(now - ChronoDuration::hours(INITIAL_LOOKBACK_HOURS)),
+    };
+    let to = settled.min(from + ChronoDuration::minutes(MAX_MINUTES_PER_PASS));
+    if to <= from {
+        return Ok(0);
+    }
+
+    let decided = decide_rounds(pool, config, from, to).await?;
+
+    sqlx::query(
+        "INSERT INTO job_watermarks (name, watermark) VALUES ($1, $2)
+         ON CONFLICT (name) DO UPDATE SET watermark = EXCLUDED.watermark",
+    )
+    .bind(CONSENSUS_WATERMARK)
+    .bind(to)
+    .execute(pool)
+    .await?;
+
+    Ok(decided)
+}
+
+/// Decides each check round once it has settled.
+pub async fn run_consensus_job(pool: PgPool) {
+    let config = consensus_config();
+    println!(
+        "Consensus job: rule {}, min locations {}, reputation weighting {}",
+        config.rule, config.min_locations, config.reputation_weighting
+    );
+    let mut interval = tokio::time::interval(Duration::from_secs(CONSENSUS_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if let Err(e) = decide_pending(&pool, &config).await {
+            println!("Consensus job error: {:?}", e);
+        }
+    }
+}
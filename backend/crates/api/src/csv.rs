@@ -0,0 +1,36 @@
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+/// Quotes a field that holds a separator, a quote or a line break.
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// An optional field, empty when `None`.
+pub fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| field(&value.to_string())).unwrap_or_default()
+}
+
+/// `header` and `rows` as a CSV download named `filename`.
+pub fn csv_response(filename: &str, header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> Response {
+    let mut body = header.join(",");
+    body.push_str("\r\n");
+    for row in rows {
+        body.push_str(&row.join(","));
+        body.push_str("\r\n");
+    }
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,442 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+    Extension,
+};
+use chrono::{NaiveDateTime, Utc};
+use config::get_config;
+use futures_util::{SinkExt, StreamExt};
+use protocol::{
+    messages::{
+        ChallengeOutgoingMessage, ErrorOutgoingMessage, IncomingMessage, OutgoingMessage, SignupIncomingMessage,
+        SignupOutgoingMessage, ValidateIncomingMessage, ValidateOutgoingMessage,
+    },
+    negotiation::{negotiate, Negotiated, UnsupportedVersion},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use sqlx::{PgPool, Row};
+use tokio::sync::{mpsc, oneshot, Notify};
+use uuid::Uuid;
+
+use crate::{
+    ingest::ingest_tick,
+    liveness::{mark_offline, record_heartbeat},
+    models::ValidatorStatus,
+    verification::FreshnessWindow,
+};
+
+/// A connection that has not signed up by then is closed.
+const SIGNUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Validators connected to this server and the validate requests waiting
+/// for their answers. Cheap to clone; all clones share one registry.
+#[derive(Clone, Default)]
+pub struct ValidatorGateway {
+    inner: Arc<GatewayInner>,
+}
+
+#[derive(Default)]
+struct GatewayInner {
+    validators: Mutex<HashMap<Uuid, ConnectedValidator>>,
+    callbacks: Mutex<HashMap<Uuid, PendingCallback>>,
+}
+
+/// A signed-up validator and the channel to its socket.
+#[derive(Debug, Clone)]
+pub struct ConnectedValidator {
+    pub validator_id: Uuid,
+    pub public_key: String,
+    pub negotiated: Negotiated,
+    pub connected_at: NaiveDateTime,
+    /// Tells a reconnected validator apart from its previous connection.
+    connection_id: Uuid,
+    sender: mpsc::UnboundedSender<OutgoingMessage>,
+    /// Tells the connection to close.
+    closing: Arc<Notify>,
+}
+
+struct PendingCallback {
+    validator_id: Uuid,
+    connection_id: Uuid,
+    reply: oneshot::Sender<ValidateIncomingMessage>,
+}
+
+#[derive(Debug)]
+pub enum DispatchError {
+    NotConnected,
+    /// The validator went away before answering.
+    Disconnected,
+    Timeout,
+}
+
+impl ValidatorGateway {
+    /// Validators connected right now.
+    pub fn connected(&self) -> Vec<ConnectedValidator> {
+        self.inner.validators.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn is_connected(&self, validator_id: Uuid) -> bool {
+        self.inner.validators.lock().unwrap().contains_key(&validator_id)
+    }
+
+    /// Unanswered validate requests per validator.
+    pub fn loads(&self) -> HashMap<Uuid, usize> {
+        let mut loads = HashMap::new();
+        for pending in self.inner.callbacks.lock().unwrap().values() {
+            *loads.entry(pending.validator_id).or_default() += 1;
+        }
+        loads
+    }
+
+    /// Asks a validator to check `url` and waits up to `timeout` for its
+    /// signed answer.
+    pub async fn dispatch(
+        &self,
+        validator_id: Uuid,
+        url: &str,
+        website_id: Uuid,
+        round_id: Uuid,
+        timeout: Duration,
+    ) -> Result<ValidateIncomingMessage, DispatchError> {
+        let validator = self
+            .inner
+            .validators
+            .lock()
+            .unwrap()
+            .get(&validator_id)
+            .cloned()
+            .ok_or(DispatchError::NotConnected)?;
+
+        let callback_id = Uuid::new_v4();
+        let (reply, answer) = oneshot::channel();
+        self.inner.callbacks.lock().unwrap().insert(
+            callback_id,
+            PendingCallback { validator_id, connection_id: validator.connection_id, reply },
+        );
+
+        let request = OutgoingMessage::Validate(ValidateOutgoingMessage {
+            url: url.to_string(),
+            callback_id,
+            website_id,
+            round_id,
+        });
+        if validator.sender.send(request).is_err() {
+            self.inner.callbacks.lock().unwrap().remove(&callback_id);
+            return Err(DispatchError::Disconnected);
+        }
+
+        match tokio::time::timeout(timeout, answer).await {
+            Ok(Ok(message)) => Ok(message),
+            Ok(Err(_)) => Err(DispatchError::Disconnected),
+            Err(_) => {
+                self.inner.callbacks.lock().unwrap().remove(&callback_id);
+                Err(DispatchError::Timeout)
+            }
+        }
+    }
+
+    /// Tells a validator why and closes its connection, e.g. after a ban.
+    pub fn disconnect(&self, validator_id: Uuid, message: String) {
+        let Some(validator) = self.inner.validators.lock().unwrap().get(&validator_id).cloned() else {
+            return;
+        };
+        send_error(&validator.sender, None, message);
+        validator.closing.notify_one();
+    }
+
+    /// Adds a validator, replacing an older connection of the same validator.
+    fn register(&self, validator: ConnectedValidator) {
+        self.inner.validators.lock().unwrap().insert(validator.validator_id, validator);
+    }
+
+    /// Removes a connection and fails the requests still waiting on it.
+    fn unregister(&self, validator_id: Uuid, connection_id: Uuid) {
+        let mut validators = self.inner.validators.lock().unwrap();
+        if validators.get(&validator_id).is_some_and(|v| v.connection_id == connection_id) {
+            validators.remove(&validator_id);
+        }
+        drop(validators);
+        self.inner
+            .callbacks
+            .lock()
+            .unwrap()
+            .retain(|_, pending| pending.connection_id != connection_id);
+    }
+
+    /// Hands an answer to the request waiting for it; `false` when no
+    /// request on this connection has that callback id, e.g. after a timeout.
+    fn complete(&self, connection_id: Uuid, message: ValidateIncomingMessage) -> bool {
+        let mut callbacks = self.inner.callbacks.lock().unwrap();
+        if callbacks
+            .get(&message.callback_id)
+            .is_none_or(|pending| pending.connection_id != connection_id)
+        {
+            return false;
+        }
+        let pending = callbacks.remove(&message.callback_id).unwrap();
+        drop(callbacks);
+        let _ = pending.reply.send(message);
+        true
+    }
+}
+
+#[derive(Debug)]
+enum SignupError {
+    UnsupportedVersion(UnsupportedVersion),
+    WrongChallenge,
+    BadSignature,
+    Banned,
+    Database(sqlx::Error),
+}
+
+impl SignupError {
+    fn message(&self) -> String {
+        match self {
+            SignupError::UnsupportedVersion(e) => e.to_string(),
+            SignupError::WrongChallenge => "Signup does not answer this connection's challenge".to_string(),
+            SignupError::BadSignature => "Invalid signature".to_string(),
+            SignupError::Banned => "Validator is banned".to_string(),
+            SignupError::Database(_) => "Failed to sign up validator".to_string(),
+        }
+    }
+}
+
+/// Checks a signature sent as a JSON array of bytes, as validators do for
+/// their signup and replies.
+fn verify_signed_message(text: &str, public_key: &str, signed_message: &str) -> bool {
+    let Ok(pubkey) = Pubkey::from_str(public_key) else {
+        return false;
+    };
+    let Ok(bytes) = serde_json::from_str::<Vec<u8>>(signed_message) else {
+        return false;
+    };
+    let Ok(signature) = Signature::try_from(bytes.as_slice()) else {
+        return false;
+    };
+    signature.verify(pubkey.as_ref(), text.as_bytes())
+}
+
+/// Verifies a signup and returns the validator's id, creating the validator
+/// on its first signup. New validators wait for an admin to approve them
+/// unless `VALIDATOR_AUTO_APPROVE` is set; banned ones are turned away.
+async fn sign_up(
+    pool: &PgPool,
+    signup: &SignupIncomingMessage,
+    challenge: &str,
+) -> Result<(Uuid, Negotiated), SignupError> {
+    let negotiated =
+        negotiate(signup.protocol_version, &signup.capabilities).map_err(SignupError::UnsupportedVersion)?;
+    if signup.challenge != challenge {
+        return Err(SignupError::WrongChallenge);
+    }
+    if !verify_signed_message(&signup.signed_text(), &signup.public_key, &signup.signed_message) {
+        return Err(SignupError::BadSignature);
+    }
+
+    let existing = sqlx::query("SELECT id, status FROM validators WHERE public_key = $1 LIMIT 1")
+        .bind(&signup.public_key)
+        .fetch_optional(pool)
+        .await
+        .map_err(SignupError::Database)?;
+    let validator_id = match existing {
+        Some(row) if row.get::<ValidatorStatus, _>("status") == ValidatorStatus::Banned => {
+            return Err(SignupError::Banned);
+        }
+        Some(row) => row.get::<Uuid, _>("id"),
+        None => {
+            let status = if get_config().validator_auto_approve {
+                ValidatorStatus::Active
+            } else {
+                ValidatorStatus::Pending
+            };
+            sqlx::query(
+                "INSERT INTO validators (ip, public_key, location, status, status_changed_at)
+                 VALUES ($1, $2, 'unknown', $3, $4) RETURNING id",
+            )
+            .bind(&signup.ip)
+            .bind(&signup.public_key)
+            .bind(status)
+            .bind(Utc::now().naive_utc())
+            .fetch_one(pool)
+            .await
+            .map_err(SignupError::Database)?
+            .get::<Uuid, _>("id")
+        }
+    };
+    Ok((validator_id, negotiated))
+}
+
+fn send_error(sender: &mpsc::UnboundedSender<OutgoingMessage>, callback_id: Option<Uuid>, message: String) {
+    let _ = sender.send(OutgoingMessage::Error(ErrorOutgoingMessage { callback_id, message }));
+}
+
+/// `GET /api/validators/ws`: the WebSocket validators connect to instead of
+/// the hub. The server sends a challenge, the validator signs it into its
+/// signup and then answers validate requests until it disconnects.
+pub(crate) async fn validator_socket_handler(
+    ws: WebSocketUpgrade,
+    Extension(pool): Extension<PgPool>,
+    Extension(gateway): Extension<ValidatorGateway>,
+) -> Response {
+    ws.on_upgrade(move |socket| serve_validator(socket, pool, gateway))
+}
+
+async fn serve_validator(socket: WebSocket, pool: PgPool, gateway: ValidatorGateway) {
+    let (mut sink, mut stream) = socket.split();
+    let (sender, mut outgoing) = mpsc::unbounded_channel::<OutgoingMessage>();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            let text = match serde_json::to_string(&message) {
+                Ok(text) => text,
+                Err(e) => {
+                    println!("Error encoding validator message: {:?}", e);
+                    continue;
+                }
+            };
+            if sink.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let connection_id = Uuid::new_v4();
+    let closing = Arc::new(Notify::new());
+    let challenge = Uuid::new_v4().simple().to_string();
+    let _ = sender.send(OutgoingMessage::Challenge(ChallengeOutgoingMessage { challenge: challenge.clone() }));
+
+    // Set once the validator has signed up
+    let mut signed_up: Option<(Uuid, String)> = None;
+    loop {
+        let next = tokio::select! {
+            next = async {
+                match signed_up {
+                    Some(_) => stream.next().await,
+                    None => tokio::time::timeout(SIGNUP_TIMEOUT, stream.next()).await.ok().flatten(),
+                }
+            } => next,
+            _ = closing.notified() => break,
+        };
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+            Some(Ok(_)) => continue,
+        };
+        let message = match serde_json::from_str::<IncomingMessage>(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                send_error(&sender, None, format!("Malformed message: {}", e));
+                continue;
+            }
+        };
+
+        match (message, &signed_up) {
+            (IncomingMessage::Signup(signup), None) => match sign_up(&pool, &signup, &challenge).await {
+                Ok((validator_id, negotiated)) => {
+                    let _ = sender.send(OutgoingMessage::Signup(SignupOutgoingMessage {
+                        validator_id,
+                        callback_id: signup.callback_id,
+                        protocol_version: negotiated.protocol_version,
+                        capabilities: negotiated.capabilities.clone(),
+                    }));
+                    gateway.register(ConnectedValidator {
+                        validator_id,
+                        public_key: signup.public_key.clone(),
+                        negotiated,
+                        connected_at: Utc::now().naive_utc(),
+                        connection_id,
+                        sender: sender.clone(),
+                        closing: closing.clone(),
+                    });
+                    signed_up = Some((validator_id, signup.public_key));
+                    if let Err(e) = record_heartbeat(&pool, validator_id).await {
+                        println!("Error recording validator heartbeat: {:?}", e);
+                    }
+                }
+                Err(e) => {
+                    if let SignupError::Database(e) = &e {
+                        println!("Error signing up validator: {:?}", e);
+                    }
+                    // The challenge is spent, so the validator has to reconnect
+                    send_error(&sender, Some(signup.callback_id), e.message());
+                    break;
+                }
+            },
+            (IncomingMessage::Signup(signup), Some(_)) => {
+                send_error(&sender, Some(signup.callback_id), "Already signed up".to_string());
+            }
+            (IncomingMessage::Validate(reply), Some((_, public_key))) => {
+                let callback_id = reply.callback_id;
+                if !verify_signed_message(&reply.signed_text(), public_key, &reply.signed_message) {
+                    send_error(&sender, Some(callback_id), "Invalid signature".to_string());
+                } else if !gateway.complete(connection_id, *reply) {
+                    send_error(&sender, Some(callback_id), "Unknown or expired callback".to_string());
+                }
+            }
+            (IncomingMessage::Validate(reply), None) => {
+                send_error(&sender, Some(reply.callback_id), "Sign up first".to_string());
+            }
+            (IncomingMessage::Heartbeat, Some((validator_id, _))) => {
+                if let Err(e) = record_heartbeat(&pool, *validator_id).await {
+                    println!("Error recording validator heartbeat: {:?}", e);
+                }
+            }
+            (IncomingMessage::Heartbeat, None) => {
+                send_error(&sender, None, "Sign up first".to_string());
+            }
+        }
+    }
+
+    if let Some((validator_id, _)) = signed_up {
+        gateway.unregister(validator_id, connection_id);
+        // Unless it has already reconnected
+        if !gateway.is_connected(validator_id)
+            && let Err(e) = mark_offline(&pool, validator_id).await
+        {
+            println!("Error marking validator offline: {:?}", e);
+        }
+    }
+    drop(sender);
+    let _ = writer.await;
+}
+
+/// Sends one website to one validator and records the answer like a report
+/// posted to `/api/ticks`.
+pub(crate) async fn check_website(
+    pool: PgPool,
+    gateway: ValidatorGateway,
+    validator_id: Uuid,
+    website_id: Uuid,
+    url: String,
+    round_id: Uuid,
+) {
+    let config = get_config();
+    let timeout = Duration::from_secs(config.validator_response_timeout_secs);
+    let reply = match gateway.dispatch(validator_id, &url, website_id, round_id, timeout).await {
+        Ok(reply) => reply,
+        Err(e) => {
+            println!("Validator {} did not check {}: {:?}", validator_id, url, e);
+            return;
+        }
+    };
+
+    let report = &reply.report;
+    if report.validator_id != validator_id || report.website_id != website_id || report.round_id != round_id {
+        println!("Validator {} answered with a report for another check", validator_id);
+        return;
+    }
+    let window = FreshnessWindow::from_config(&config);
+    if let Err(e) = ingest_tick(&pool, report, window, config.cost_per_validation).await {
+        match e.database_error() {
+            Some(e) => println!("Error recording tick: {:?}", e),
+            None => println!("Tick from {} rejected: {}", validator_id, e.message()),
+        }
+    }
+}
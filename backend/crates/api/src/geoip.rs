@@ -0,0 +1,178 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::Utc;
+use config::{get_config, Config};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+const RESOLVER_INTERVAL_SECS: u64 = 300;
+
+/// Location details of one IP address. Every field is optional since the
+/// databases only cover part of the address space.
+#[derive(Debug, Default)]
+pub struct GeoLocation {
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub asn: Option<i64>,
+    pub as_organization: Option<String>,
+}
+
+/// Reads validator locations from local MaxMind-format databases.
+pub struct GeoResolver {
+    city: Reader<Vec<u8>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+fn english_name(names: Option<&std::collections::BTreeMap<&str, &str>>) -> Option<String> {
+    names.and_then(|names| names.get("en")).map(|name| name.to_string())
+}
+
+/// Validators register with a bare IP or with `ip:port`.
+fn parse_ip(ip: &str) -> Option<IpAddr> {
+    let ip = ip.trim();
+    ip.parse::<IpAddr>()
+        .ok()
+        .or_else(|| ip.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+impl GeoResolver {
+    /// Opens the databases from the config; `Ok(None)` when GeoIP is not
+    /// configured.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, MaxMindDBError> {
+        let Some(path) = config.geoip_database.as_deref() else {
+            return Ok(None);
+        };
+        let city = Reader::open_readfile(path)?;
+        let asn = match config.geoip_asn_database.as_deref() {
+            Some(path) => Some(Reader::open_readfile(path)?),
+            None => None,
+        };
+        Ok(Some(GeoResolver { city, asn }))
+    }
+
+    /// `Ok(None)` when the address is not in the database.
+    pub fn resolve(&self, ip: IpAddr) -> Result<Option<GeoLocation>, MaxMindDBError> {
+        let city = match self.city.lookup::<geoip2::City>(ip) {
+            Ok(city) => Some(city),
+            Err(MaxMindDBError::AddressNotFoundError(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let asn_reader = self.asn.as_ref().unwrap_or(&self.city);
+        let asn = match asn_reader.lookup::<geoip2::Asn>(ip) {
+            Ok(asn) => Some(asn),
+            Err(MaxMindDBError::AddressNotFoundError(_)) => None,
+            Err(e) => return Err(e),
+        };
+        if city.is_none() && asn.is_none() {
+            return Ok(None);
+        }
+
+        let mut location = GeoLocation::default();
+        if let Some(city) = city {
+            location.country = city.country.and_then(|country| country.iso_code).map(str::to_string);
+            location.region = city
+                .subdivisions
+                .as_ref()
+                .and_then(|subdivisions| subdivisions.first())
+                .and_then(|subdivision| english_name(subdivision.names.as_ref()).or(subdivision.iso_code.map(str::to_string)));
+            location.city = city.city.and_then(|city| english_name(city.names.as_ref()));
+            if let Some(coordinates) = city.location {
+                location.latitude = coordinates.latitude;
+                location.longitude = coordinates.longitude;
+            }
+        }
+        if let Some(asn) = asn {
+            location.asn = asn.autonomous_system_number.map(i64::from);
+            location.as_organization = asn.autonomous_system_organization.map(str::to_string);
+        }
+        Ok(Some(location))
+    }
+}
+
+/// Resolves the location of validators from their IP. With `all` every
+/// validator is re-resolved, otherwise only those never resolved. A
+/// validator's `location` becomes its country code; it keeps its previous
+/// location when the country is unknown. Returns the number of validators
+/// located.
+pub async fn resolve_validator_locations(
+    pool: &PgPool,
+    resolver: &GeoResolver,
+    all: bool,
+) -> Result<usize, sqlx::Error> {
+    let rows = sqlx::query("SELECT id, ip FROM validators WHERE $1 OR geo_resolved_at IS NULL")
+        .bind(all)
+        .fetch_all(pool)
+        .await?;
+
+    let mut located = 0;
+    for row in &rows {
+        let validator_id = row.get::<Uuid, _>("id");
+        let ip = row.get::<String, _>("ip");
+        let location = match parse_ip(&ip).map(|ip| resolver.resolve(ip)) {
+            Some(Ok(location)) => location,
+            Some(Err(e)) => {
+                println!("Error resolving location of validator {}: {:?}", validator_id, e);
+                None
+            }
+            None => None,
+        };
+        let location = match location {
+            Some(location) => {
+                located += 1;
+                location
+            }
+            None => GeoLocation::default(),
+        };
+
+        // Unresolvable validators are marked too, so they are only retried
+        // by a full re-resolve
+        sqlx::query(
+            "UPDATE validators
+             SET location = COALESCE($2, location), country = $2, region = $3, city = $4,
+                 latitude = $5, longitude = $6, asn = $7, as_organization = $8, geo_resolved_at = $9
+             WHERE id = $1",
+        )
+        .bind(validator_id)
+        .bind(&location.country)
+        .bind(&location.region)
+        .bind(&location.city)
+        .bind(location.latitude)
+        .bind(location.longitude)
+        .bind(location.asn)
+        .bind(&location.as_organization)
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(located)
+}
+
+/// Resolves newly registered validators. Does nothing when GeoIP is not
+/// configured or the database cannot be opened.
+pub async fn run_geoip_resolver(pool: PgPool) {
+    let resolver = match GeoResolver::from_config(&get_config()) {
+        Ok(Some(resolver)) => resolver,
+        Ok(None) => return,
+        Err(e) => {
+            println!("GeoIP resolver disabled, failed to open database: {:?}", e);
+            return;
+        }
+    };
+    let mut interval = tokio::time::interval(Duration::from_secs(RESOLVER_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        match resolve_validator_locations(&pool, &resolver, false).await {
+            Ok(0) => {}
+            Ok(located) => println!("GeoIP resolver: located {} validators", located),
+            Err(e) => println!("GeoIP resolver error: {:?}", e),
+        }
+    }
+}
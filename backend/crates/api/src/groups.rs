@@ -0,0 +1,297 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{GroupAssign, GroupCreate, UserIdQuery, WebsiteTagQuery, WebsiteTags},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+const MAX_TAG_LEN: usize = 64;
+
+/// Rolls the current status of every website in a group up into one value.
+fn aggregate_status(up: i64, degraded: i64, down: i64) -> &'static str {
+    match (up, degraded, down) {
+        (0, 0, 0) => "unknown",
+        (_, 0, 0) => "up",
+        (0, 0, _) => "down",
+        _ => "partial_outage",
+    }
+}
+
+async fn owns_website(pool: &PgPool, website_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT 1 FROM websites WHERE id = $1 AND user_id = $2 AND disabled = false")
+        .bind(website_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.is_some())
+}
+
+pub(crate) async fn add_tags_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Json(payload): Json<WebsiteTags>,
+) -> ApiJsonResponse {
+    let tags: Vec<String> = payload
+        .tags
+        .iter()
+        .map(|tag| tag.trim().to_string())
+        .collect();
+    if tags.iter().any(|tag| tag.is_empty() || tag.chars().count() > MAX_TAG_LEN) {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("Tags must be between 1 and 64 characters"),
+        );
+    }
+
+    match owns_website(&pool, website_id, payload.user_id).await {
+        Ok(true) => {}
+        Ok(false) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Website not found")),
+        Err(e) => {
+            println!("Error fetching website: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to add tags"),
+            );
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO website_tags (website_id, tag)
+         SELECT $1, UNNEST($2::TEXT[])
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(website_id)
+    .bind(&tags)
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(_) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({"id": website_id, "tags": tags}),
+                Some("Tags added successfully".to_string()),
+            ),
+        ),
+        Err(e) => {
+            println!("Error adding tags: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to add tags"),
+            )
+        }
+    }
+}
+
+pub(crate) async fn remove_tag_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Query(query): Query<WebsiteTagQuery>,
+) -> ApiJsonResponse {
+    let result = sqlx::query(
+        "DELETE FROM website_tags wt
+         USING websites w
+         WHERE w.id = wt.website_id AND wt.website_id = $1 AND w.user_id = $2 AND wt.tag = $3",
+    )
+    .bind(website_id)
+    .bind(query.user_id)
+    .bind(query.tag.trim())
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({"id": website_id, "tag": query.tag.trim()}),
+                Some("Tag removed successfully".to_string()),
+            ),
+        ),
+        Ok(_) => ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Tag not found")),
+        Err(e) => {
+            println!("Error removing tag: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to remove tag"),
+            )
+        }
+    }
+}
+
+pub(crate) async fn create_group_handler(
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<GroupCreate>,
+) -> ApiJsonResponse {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("Group name is required"));
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO website_groups (user_id, name) VALUES ($1, $2)
+         ON CONFLICT (user_id, name) DO NOTHING
+         RETURNING id",
+    )
+    .bind(payload.user_id)
+    .bind(name)
+    .fetch_optional(&pool)
+    .await;
+
+    match result {
+        Ok(Some(row)) => ApiJsonResponse(
+            StatusCode::CREATED,
+            json_success(
+                json!({"id": row.get::<Uuid, _>("id"), "name": name, "userId": payload.user_id}),
+                Some("Group created successfully".to_string()),
+            ),
+        ),
+        Ok(None) => ApiJsonResponse(
+            StatusCode::CONFLICT,
+            json_error("A group with this name already exists"),
+        ),
+        Err(e) => {
+            println!("Error creating group: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to create group"),
+            )
+        }
+    }
+}
+
+pub(crate) async fn get_groups_handler(
+    Extension(pool): Extension<PgPool>,
+    Query(query): Query<UserIdQuery>,
+) -> ApiJsonResponse {
+    // The current status of a website is its latest conclusive consensus round.
+    let result = sqlx::query(
+        "SELECT g.id, g.name,
+                COUNT(w.id) AS total,
+                COUNT(*) FILTER (WHERE latest.status = 'up') AS up,
+                COUNT(*) FILTER (WHERE latest.status = 'degraded') AS degraded,
+                COUNT(*) FILTER (WHERE latest.status = 'down') AS down
+         FROM website_groups g
+         LEFT JOIN websites w ON w.group_id = g.id AND w.disabled = false
+         LEFT JOIN LATERAL (
+             SELECT r.status FROM website_status_rounds r
+             WHERE r.website_id = w.id AND r.status <> 'inconclusive'
+             ORDER BY r.round_start DESC
+             LIMIT 1
+         ) latest ON true
+         WHERE g.user_id = $1
+         GROUP BY g.id, g.name
+         ORDER BY g.name",
+    )
+    .bind(query.user_id)
+    .fetch_all(&pool)
+    .await;
+
+    match result {
+        Ok(rows) => {
+            let groups = rows
+                .iter()
+                .map(|row| {
+                    let up = row.get::<i64, _>("up");
+                    let degraded = row.get::<i64, _>("degraded");
+                    let down = row.get::<i64, _>("down");
+                    json!({
+                        "id": row.get::<Uuid, _>("id"),
+                        "name": row.get::<String, _>("name"),
+                        "websiteCount": row.get::<i64, _>("total"),
+                        "up": up,
+                        "degraded": degraded,
+                        "down": down,
+                        "status": aggregate_status(up, degraded, down)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            ApiJsonResponse(StatusCode::OK, json_success(json!({"groups": groups}), None))
+        }
+        Err(e) => {
+            println!("Error fetching groups: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch groups"),
+            )
+        }
+    }
+}
+
+pub(crate) async fn delete_group_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<UserIdQuery>,
+) -> ApiJsonResponse {
+    let result = sqlx::query("DELETE FROM website_groups WHERE id = $1 AND user_id = $2")
+        .bind(group_id)
+        .bind(query.user_id)
+        .execute(&pool)
+        .await;
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({"id": group_id}),
+                Some("Group deleted successfully".to_string()),
+            ),
+        ),
+        Ok(_) => ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Group not found")),
+        Err(e) => {
+            println!("Error deleting group: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to delete group"),
+            )
+        }
+    }
+}
+
+/// Moves a website into a group, or out of any group when `group_id` is null.
+pub(crate) async fn assign_group_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Json(payload): Json<GroupAssign>,
+) -> ApiJsonResponse {
+    let result = sqlx::query(
+        "UPDATE websites SET group_id = $3
+         WHERE id = $1 AND user_id = $2 AND disabled = false
+           AND ($3::UUID IS NULL OR EXISTS (
+               SELECT 1 FROM website_groups g WHERE g.id = $3 AND g.user_id = $2
+           ))",
+    )
+    .bind(website_id)
+    .bind(payload.user_id)
+    .bind(payload.group_id)
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({"id": website_id, "groupId": payload.group_id}),
+                Some("Website group updated successfully".to_string()),
+            ),
+        ),
+        Ok(_) => ApiJsonResponse(
+            StatusCode::NOT_FOUND,
+            json_error("Website or group not found"),
+        ),
+        Err(e) => {
+            println!("Error updating website group: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to update website group"),
+            )
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::time::Duration;
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::Utc;
+use config::get_config;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::HeartbeatCreate,
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+const EVALUATOR_INTERVAL_SECS: u64 = 30;
+
+pub(crate) async fn create_heartbeat_handler(
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<HeartbeatCreate>,
+) -> ApiJsonResponse {
+    let grace_seconds = payload.grace_seconds.unwrap_or(0);
+    if payload.period_seconds <= 0 || grace_seconds < 0 {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("Period must be positive and grace cannot be negative"),
+        );
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    let ping_url = format!("{}/api/heartbeat/{}", get_config().public_url, token);
+    let next_due_at = Utc::now().naive_utc() + chrono::Duration::seconds(payload.period_seconds as i64);
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            println!("Failed to start transaction: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to create heartbeat monitor"),
+            );
+        }
+    };
+
+    let website_id = match sqlx::query(
+        "INSERT INTO websites (url, user_id, disabled, monitor_type)
+         VALUES ($1, $2, false, 'heartbeat')
+         RETURNING id",
+    )
+    .bind(&ping_url)
+    .bind(payload.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(row) => row.get::<Uuid, _>("id"),
+        Err(e) => {
+            println!("DB error: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to create heartbeat monitor"),
+            );
+        }
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO heartbeat_monitors (website_id, token, period_seconds, grace_seconds, next_due_at)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(website_id)
+    .bind(&token)
+    .bind(payload.period_seconds)
+    .bind(grace_seconds)
+    .bind(next_due_at)
+    .execute(&mut *tx)
+    .await;
+
+    if let Err(e) = result {
+        println!("DB error: {:?}", e);
+        return ApiJsonResponse(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            json_error("Failed to create heartbeat monitor"),
+        );
+    }
+
+    if let Err(e) = tx.commit().await {
+        println!("Failed to commit transaction: {:?}", e);
+        return ApiJsonResponse(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            json_error("Failed to create heartbeat monitor"),
+        );
+    }
+
+    ApiJsonResponse(
+        StatusCode::CREATED,
+        json_success(
+            json!({
+                "id": website_id,
+                "url": ping_url,
+                "userId": payload.user_id,
+                "periodSeconds": payload.period_seconds,
+                "graceSeconds": grace_seconds
+            }),
+            Some("Heartbeat monitor created successfully!".to_string()),
+        ),
+    )
+}
+
+/// A job finished successfully. The tick latency is the run duration when the
+/// job announced itself through `/start` first.
+pub(crate) async fn heartbeat_ping_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(token): Path<String>,
+) -> ApiJsonResponse {
+    record_ping(&pool, &token, "Good").await
+}
+
+pub(crate) async fn heartbeat_fail_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(token): Path<String>,
+) -> ApiJsonResponse {
+    record_ping(&pool, &token, "Bad").await
+}
+
+pub(crate) async fn heartbeat_start_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(token): Path<String>,
+) -> ApiJsonResponse {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query(
+        "UPDATE heartbeat_monitors hm SET last_started_at = $2
+         FROM websites w
+         WHERE w.id = hm.website_id AND w.disabled = false AND hm.token = $1",
+    )
+    .bind(&token)
+    .bind(now)
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            ApiJsonResponse(StatusCode::OK, json_success(json!({"startedAt": now}), None))
+        }
+        Ok(_) => ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Heartbeat monitor not found")),
+        Err(e) => {
+            println!("Error recording heartbeat start: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to record heartbeat"),
+            )
+        }
+    }
+}
+
+async fn record_ping(pool: &PgPool, token: &str, status: &str) -> ApiJsonResponse {
+    let now = Utc::now().naive_utc();
+
+    // Reset the deadline and clear the start marker in one step, handing back
+    // what we need to write the tick.
+    let monitor = sqlx::query(
+        "UPDATE heartbeat_monitors hm
+         SET last_ping_at = $2,
+             last_started_at = NULL,
+             next_due_at = $2 + make_interval(secs => hm.period_seconds)
+         FROM websites w,
+              (SELECT website_id, last_started_at FROM heartbeat_monitors WHERE token = $1 FOR UPDATE) prev
+         WHERE w.id = hm.website_id AND prev.website_id = hm.website_id AND w.disabled = false
+         RETURNING hm.website_id,
+                   EXTRACT(EPOCH FROM ($2 - prev.last_started_at))::FLOAT8 * 1000 AS duration_ms",
+    )
+    .bind(token)
+    .bind(now)
+    .fetch_optional(pool)
+    .await;
+
+    let (website_id, duration_ms) = match monitor {
+        Ok(Some(row)) => (
+            row.get::<Uuid, _>("website_id"),
+            row.get::<Option<f64>, _>("duration_ms"),
+        ),
+        Ok(None) => {
+            return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Heartbeat monitor not found"));
+        }
+        Err(e) => {
+            println!("Error recording heartbeat: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to record heartbeat"),
+            );
+        }
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO website_ticks (website_id, validator_id, status, latency, created_at)
+         VALUES ($1, NULL, $2, $3, $4)",
+    )
+    .bind(website_id)
+    .bind(status)
+    .bind(duration_ms.unwrap_or(0.0))
+    .bind(now)
+    .execute(pool)
+    .await;
+
+    match result {
+        Ok(_) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(json!({"websiteId": website_id, "status": status}), None),
+        ),
+        Err(e) => {
+            println!("Error inserting heartbeat tick: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to record heartbeat"),
+            )
+        }
+    }
+}
+
+/// Marks heartbeat monitors down once their period plus grace has elapsed
+/// without a ping. One `Bad` tick is written per missed period.
+pub async fn run_heartbeat_evaluator(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(EVALUATOR_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        match evaluate_heartbeats(&pool).await {
+            Ok(0) => {}
+            Ok(missed) => println!("Heartbeat evaluator: {} monitor(s) missed their deadline", missed),
+            Err(e) => println!("Heartbeat evaluator error: {:?}", e),
+        }
+    }
+}
+
+async fn evaluate_heartbeats(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query(
+        "WITH overdue AS (
+             UPDATE heartbeat_monitors hm
+             SET next_due_at = GREATEST(hm.next_due_at + make_interval(secs => hm.period_seconds), $1)
+             FROM websites w
+             WHERE w.id = hm.website_id
+               AND w.disabled = false
+               AND hm.next_due_at + make_interval(secs => hm.grace_seconds) < $1
+             RETURNING hm.website_id
+         )
+         INSERT INTO website_ticks (website_id, validator_id, status, latency, created_at)
+         SELECT website_id, NULL, 'Bad', 0, $1 FROM overdue",
+    )
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension,
+};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Incident, IncidentQuery},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+const EVALUATOR_INTERVAL_SECS: u64 = 60;
+/// How far back the evaluator looks for consensus rounds.
+const STATUS_WINDOW_SECS: i64 = 600;
+const DEFAULT_INCIDENTS_LIMIT: i64 = 50;
+const MAX_INCIDENTS_LIMIT: i64 = 500;
+
+/// Opens an incident for every website whose latest conclusive consensus
+/// round is down and resolves the open incident of every website whose
+/// latest round is up or degraded. An incident starts at the first down
+/// round of the streak and takes its failing validators and reasons from
+/// it; websites without recent conclusive rounds keep their state.
+pub async fn evaluate_incidents(pool: &PgPool) -> Result<(u64, u64), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let since = now - chrono::Duration::seconds(STATUS_WINDOW_SECS);
+
+    let opened = sqlx::query(
+        "WITH latest AS (
+             SELECT DISTINCT ON (website_id) website_id, status
+             FROM website_status_rounds
+             WHERE round_start >= $1 AND status <> 'inconclusive'
+             ORDER BY website_id, round_start DESC
+         )
+         INSERT INTO incidents (website_id, started_at, first_failing_validators, error_reasons)
+         SELECT l.website_id, first_down.round_start, first_down.failing_validators, first_down.error_reasons
+         FROM latest l
+         JOIN websites w ON w.id = l.website_id AND w.disabled = false
+         CROSS JOIN LATERAL (
+             SELECT r.round_start, r.failing_validators, r.error_reasons
+             FROM website_status_rounds r
+             WHERE r.website_id = l.website_id AND r.status = 'down' AND r.round_start >= $1
+               AND r.round_start > COALESCE(
+                   (SELECT MAX(p.round_start) FROM website_status_rounds p
+                    WHERE p.website_id = l.website_id AND p.round_start >= $1
+                      AND p.status IN ('up', 'degraded')),
+                   '-infinity'
+               )
+             ORDER BY r.round_start
+             LIMIT 1
+         ) first_down
+         WHERE l.status = 'down'
+         ON CONFLICT (website_id) WHERE resolved_at IS NULL DO NOTHING",
+    )
+    .bind(since)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    let resolved = sqlx::query(
+        "UPDATE incidents i
+         SET resolved_at = GREATEST(recovered.recovered_at, i.started_at),
+             duration_seconds = EXTRACT(EPOCH FROM GREATEST(recovered.recovered_at, i.started_at) - i.started_at)::INTEGER
+         FROM (
+             -- Recovery is the first healthy round after the last down round in the window
+             SELECT website_id,
+                    MIN(round_start) FILTER (
+                        WHERE status IN ('up', 'degraded') AND round_start > COALESCE(last_down, '-infinity')
+                    ) AS recovered_at,
+                    (array_agg(status ORDER BY round_start DESC))[1] AS latest_status
+             FROM (
+                 SELECT website_id, round_start, status,
+                        MAX(round_start) FILTER (WHERE status = 'down') OVER (PARTITION BY website_id) AS last_down
+                 FROM website_status_rounds
+                 WHERE round_start >= $1 AND status <> 'inconclusive'
+             ) recent
+             GROUP BY website_id
+         ) recovered
+         WHERE i.website_id = recovered.website_id AND i.resolved_at IS NULL
+           AND recovered.latest_status IN ('up', 'degraded')",
+    )
+    .bind(since)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok((opened, resolved))
+}
+
+pub async fn run_incident_evaluator(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(EVALUATOR_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        match evaluate_incidents(&pool).await {
+            Ok((0, 0)) => {}
+            Ok((opened, resolved)) => {
+                println!("Incident evaluator: {} opened, {} resolved", opened, resolved)
+            }
+            Err(e) => println!("Incident evaluator error: {:?}", e),
+        }
+    }
+}
+
+async fn fetch_incidents(
+    pool: &PgPool,
+    user_id: Uuid,
+    website_id: Option<Uuid>,
+    query: &IncidentQuery,
+) -> Result<Vec<Incident>, sqlx::Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_INCIDENTS_LIMIT).clamp(1, MAX_INCIDENTS_LIMIT);
+    sqlx::query_as::<_, Incident>(
+        "SELECT i.id, i.website_id, w.url, i.started_at, i.resolved_at, i.duration_seconds,
+                i.first_failing_validators, i.error_reasons
+         FROM incidents i
+         JOIN websites w ON w.id = i.website_id
+         WHERE w.user_id = $1
+           AND ($2::UUID IS NULL OR i.website_id = $2)
+           AND ($3::TEXT IS NULL
+                OR ($3 = 'open' AND i.resolved_at IS NULL)
+                OR ($3 = 'resolved' AND i.resolved_at IS NOT NULL))
+         ORDER BY i.started_at DESC
+         LIMIT $4",
+    )
+    .bind(user_id)
+    .bind(website_id)
+    .bind(query.status.as_deref())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+fn incidents_response(result: Result<Vec<Incident>, sqlx::Error>) -> ApiJsonResponse {
+    match result {
+        Ok(incidents) => ApiJsonResponse(StatusCode::OK, json_success(json!({"incidents": incidents}), None)),
+        Err(e) => {
+            println!("Error fetching incidents: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch incidents"),
+            )
+        }
+    }
+}
+
+fn invalid_status(query: &IncidentQuery) -> Option<ApiJsonResponse> {
+    match query.status.as_deref() {
+        None | Some("open") | Some("resolved") => None,
+        Some(_) => Some(ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("status must be one of open, resolved"),
+        )),
+    }
+}
+
+pub(crate) async fn get_website_incidents_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Query(query): Query<IncidentQuery>,
+) -> ApiJsonResponse {
+    if let Some(response) = invalid_status(&query) {
+        return response;
+    }
+    incidents_response(fetch_incidents(&pool, query.user_id, Some(website_id), &query).await)
+}
+
+/// Incidents across every website of the account, newest first.
+pub(crate) async fn get_incident_feed_handler(
+    Extension(pool): Extension<PgPool>,
+    Query(query): Query<IncidentQuery>,
+) -> ApiJsonResponse {
+    if let Some(response) = invalid_status(&query) {
+        return response;
+    }
+    incidents_response(fetch_incidents(&pool, query.user_id, None, &query).await)
+}
@@ -0,0 +1,274 @@
+use axum::{http::StatusCode, Extension, Json};
+use chrono::Utc;
+use config::get_config;
+use protocol::tick::TickReport;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{TickSubmission, ValidatorStatus},
+    routes::{json_error, json_success, ApiJsonResponse},
+    verification::{verify_report, FreshnessWindow, PgNonceStore, VerificationError},
+};
+
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Why a report was not recorded.
+#[derive(Debug)]
+pub enum IngestError {
+    Invalid(&'static str),
+    UnknownValidator,
+    /// The validator is pending, suspended or banned.
+    InactiveValidator,
+    UnknownWebsite,
+    /// The validator was not assigned this website in this round.
+    NotAssigned,
+    Verification(VerificationError),
+    Duplicate,
+    Database(sqlx::Error),
+}
+
+impl IngestError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            IngestError::Invalid(_) => StatusCode::BAD_REQUEST,
+            IngestError::UnknownValidator => StatusCode::UNAUTHORIZED,
+            IngestError::InactiveValidator => StatusCode::FORBIDDEN,
+            IngestError::UnknownWebsite => StatusCode::NOT_FOUND,
+            IngestError::NotAssigned => StatusCode::FORBIDDEN,
+            IngestError::Verification(error) => match error {
+                VerificationError::MalformedNonce
+                | VerificationError::Stale
+                | VerificationError::FromTheFuture => StatusCode::BAD_REQUEST,
+                VerificationError::BadSignature => StatusCode::UNAUTHORIZED,
+                VerificationError::Replayed => StatusCode::CONFLICT,
+                VerificationError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            IngestError::Duplicate => StatusCode::CONFLICT,
+            IngestError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub(crate) fn message(&self) -> &'static str {
+        match self {
+            IngestError::Invalid(message) => message,
+            IngestError::UnknownValidator => "Unknown validator",
+            IngestError::InactiveValidator => "Validator is not active",
+            IngestError::UnknownWebsite => "Unknown website",
+            IngestError::NotAssigned => "No check was assigned for this round",
+            IngestError::Verification(error) => match error {
+                VerificationError::MalformedNonce => "nonce must be 16 to 128 characters of [A-Za-z0-9_-]",
+                VerificationError::Stale => "Stale report",
+                VerificationError::FromTheFuture => "Report timestamp is in the future",
+                VerificationError::BadSignature => "Invalid signature",
+                VerificationError::Replayed => "Replayed report",
+                VerificationError::Database(_) => "Failed to record tick",
+            },
+            IngestError::Duplicate => "Already reported for this round",
+            IngestError::Database(_) => "Failed to record tick",
+        }
+    }
+}
+
+impl IngestError {
+    pub(crate) fn database_error(&self) -> Option<&sqlx::Error> {
+        match self {
+            IngestError::Database(e) | IngestError::Verification(VerificationError::Database(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for IngestError {
+    fn from(e: sqlx::Error) -> Self {
+        IngestError::Database(e)
+    }
+}
+
+fn validate(report: &TickReport) -> Result<(), IngestError> {
+    if !matches!(report.status.as_str(), "Good" | "Bad") {
+        return Err(IngestError::Invalid("status must be one of Good, Bad"));
+    }
+    if !report.latency.is_finite() || report.latency < 0.0 {
+        return Err(IngestError::Invalid("latency must be a non-negative number"));
+    }
+    if report.http_status_code.is_some_and(|code| !(100..=599).contains(&code)) {
+        return Err(IngestError::Invalid("httpStatusCode must be between 100 and 599"));
+    }
+    if report.response_size_bytes.is_some_and(|size| size < 0) {
+        return Err(IngestError::Invalid("responseSizeBytes must not be negative"));
+    }
+    let timings = [
+        report.timings.dns_ms,
+        report.timings.connect_ms,
+        report.timings.tls_ms,
+        report.timings.ttfb_ms,
+    ];
+    if timings.iter().flatten().any(|ms| !ms.is_finite() || *ms < 0.0) {
+        return Err(IngestError::Invalid("timings must be non-negative numbers"));
+    }
+    Ok(())
+}
+
+/// Verifies a report against the check assigned to the validator and records
+/// it together with the validator's payout
+/// credit and daily earnings in one transaction. Returns the id of the new tick.
+pub async fn ingest_tick(
+    pool: &PgPool,
+    report: &TickReport,
+    window: FreshnessWindow,
+    cost: i32,
+) -> Result<Uuid, IngestError> {
+    validate(report)?;
+
+    let mut tx = pool.begin().await?;
+
+    // Lock the validator so concurrent reports credit payouts one at a time
+    let validator = sqlx::query("SELECT public_key, status FROM validators WHERE id = $1 FOR UPDATE")
+        .bind(report.validator_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(IngestError::UnknownValidator)?;
+    let public_key = validator.get::<String, _>("public_key");
+    // Only active validators are credited
+    if validator.get::<ValidatorStatus, _>("status") != ValidatorStatus::Active {
+        return Err(IngestError::InactiveValidator);
+    }
+
+    verify_report(report, &public_key, Utc::now(), window, &mut PgNonceStore(&mut tx))
+        .await
+        .map_err(IngestError::Verification)?;
+
+    // Only checks the scheduler handed out earn credits
+    let assigned = sqlx::query(
+        "SELECT 1 FROM check_assignments WHERE round_id = $1 AND website_id = $2 AND validator_id = $3",
+    )
+    .bind(report.round_id)
+    .bind(report.website_id)
+    .bind(report.validator_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if assigned.is_none() {
+        return Err(IngestError::NotAssigned);
+    }
+
+    let inserted = sqlx::query(
+        "INSERT INTO website_ticks
+             (website_id, validator_id, round_id, created_at, status, latency, http_status_code, error_class,
+              response_size_bytes, dns_ms, connect_ms, tls_ms, ttfb_ms)
+         SELECT w.id, $2, $3, (now() AT TIME ZONE 'utc'), $4, $5, $6, $7, $8, $9, $10, $11, $12
+         FROM websites w
+         WHERE w.id = $1 AND w.disabled = false
+         ON CONFLICT (validator_id, website_id, round_id) WHERE round_id IS NOT NULL DO NOTHING
+         RETURNING id",
+    )
+    .bind(report.website_id)
+    .bind(report.validator_id)
+    .bind(report.round_id)
+    .bind(&report.status)
+    .bind(report.latency)
+    .bind(report.http_status_code)
+    .bind(report.error_class.map(|class| class.as_str()))
+    .bind(report.response_size_bytes)
+    .bind(report.timings.dns_ms)
+    .bind(report.timings.connect_ms)
+    .bind(report.timings.tls_ms)
+    .bind(report.timings.ttfb_ms)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let tick_id = match inserted {
+        Some(row) => row.get::<Uuid, _>("id"),
+        None => {
+            let website = sqlx::query("SELECT 1 FROM websites WHERE id = $1 AND disabled = false")
+                .bind(report.website_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+            return Err(if website.is_some() {
+                IngestError::Duplicate
+            } else {
+                IngestError::UnknownWebsite
+            });
+        }
+    };
+
+    sqlx::query("UPDATE validators SET pending_payouts = pending_payouts + $1 WHERE id = $2")
+        .bind(cost)
+        .bind(report.validator_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO validator_earnings_daily (validator_id, day, ticks, credits)
+         VALUES ($1, (now() AT TIME ZONE 'utc')::DATE, 1, $2)
+         ON CONFLICT (validator_id, day) DO UPDATE
+         SET ticks = validator_earnings_daily.ticks + 1,
+             credits = validator_earnings_daily.credits + EXCLUDED.credits",
+    )
+    .bind(report.validator_id)
+    .bind(cost as i64)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(tick_id)
+}
+
+/// `POST /api/ticks`. A single report answers with its own status code; a
+/// batch answers 200 with one result per report, in order, keyed by nonce.
+pub(crate) async fn submit_ticks_handler(
+    Extension(pool): Extension<PgPool>,
+    Json(submission): Json<TickSubmission>,
+) -> ApiJsonResponse {
+    let config = get_config();
+    let window = FreshnessWindow::from_config(&config);
+    let cost = config.cost_per_validation;
+
+    match submission {
+        TickSubmission::Single(report) => match ingest_tick(&pool, &report, window, cost).await {
+            Ok(tick_id) => ApiJsonResponse(
+                StatusCode::CREATED,
+                json_success(json!({"id": tick_id}), Some("Tick recorded".to_string())),
+            ),
+            Err(e) => {
+                if let Some(e) = e.database_error() {
+                    println!("Error recording tick: {:?}", e);
+                }
+                ApiJsonResponse(e.status_code(), json_error(e.message()))
+            }
+        },
+        TickSubmission::Batch { ticks } => {
+            if ticks.is_empty() || ticks.len() > MAX_BATCH_SIZE {
+                return ApiJsonResponse(
+                    StatusCode::BAD_REQUEST,
+                    json_error("A batch must contain between 1 and 500 ticks"),
+                );
+            }
+            let mut results = Vec::with_capacity(ticks.len());
+            let mut accepted = 0;
+            for report in &ticks {
+                let result = match ingest_tick(&pool, report, window, cost).await {
+                    Ok(tick_id) => {
+                        accepted += 1;
+                        json!({"nonce": report.nonce, "status": "accepted", "id": tick_id})
+                    }
+                    Err(e) => {
+                        if let Some(e) = e.database_error() {
+                            println!("Error recording tick: {:?}", e);
+                        }
+                        let status = match e {
+                            IngestError::Duplicate | IngestError::Verification(VerificationError::Replayed) => "duplicate",
+                            _ => "rejected",
+                        };
+                        json!({"nonce": report.nonce, "status": status, "error": e.message()})
+                    }
+                };
+                results.push(result);
+            }
+            ApiJsonResponse(
+                StatusCode::OK,
+                json_success(json!({"accepted": accepted, "results": results}), None),
+            )
+        }
+    }
+}
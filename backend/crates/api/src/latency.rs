@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{LatencyBucket, LatencyQuery, LatencySplit, LatencyStats},
+    rollups::{ceil_to_day, ceil_to_hour, truncate_to_day, truncate_to_hour, LatencySketch, RollupRow},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+const DEFAULT_WINDOW_HOURS: i64 = 24;
+const DEFAULT_BUCKETS: i32 = 10;
+const MAX_BUCKETS: i32 = 100;
+
+/// Windows longer than this are read from the daily rollups.
+const DAILY_ROLLUP_THRESHOLD_DAYS: i64 = 7;
+
+fn split_key_sql(split: Option<LatencySplit>) -> &'static str {
+    match split {
+        None => "NULL::TEXT",
+        Some(LatencySplit::Validator) => "r.validator_id::TEXT",
+        Some(LatencySplit::Location) => "v.location",
+    }
+}
+
+/// Widens `[from, to)` to the bucket boundaries of the rollup table that
+/// serves it, returning the table and the aligned window.
+pub(crate) fn rollup_window(
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> (&'static str, NaiveDateTime, NaiveDateTime) {
+    if to - from > chrono::Duration::days(DAILY_ROLLUP_THRESHOLD_DAYS) {
+        ("tick_rollups_daily", truncate_to_day(from), ceil_to_day(to))
+    } else {
+        ("tick_rollups_hourly", truncate_to_hour(from), ceil_to_hour(to))
+    }
+}
+
+/// Latency distribution of successful checks of one website, read from the
+/// rollups. Failed checks are left out since their latency is a timeout or
+/// placeholder rather than a measurement.
+///
+/// min, max and mean are exact; percentiles come from the merged sketches.
+/// The histogram buckets split `[0, max]` evenly, using the same bounds for
+/// every group so groups can be compared directly.
+pub(crate) async fn latency_stats(
+    pool: &PgPool,
+    website_id: Uuid,
+    table: &str,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    split: Option<LatencySplit>,
+    buckets: i32,
+) -> Result<Vec<LatencyStats>, sqlx::Error> {
+    let key_sql = split_key_sql(split);
+    let rows = sqlx::query(&format!(
+        "SELECT {key_sql} AS key, r.up_count, r.latency_sum, r.latency_min, r.latency_max, r.latency_sketch
+         FROM {table} r
+         LEFT JOIN validators v ON v.id = r.validator_id
+         WHERE r.website_id = $1 AND r.bucket_start >= $2 AND r.bucket_start < $3"
+    ))
+    .bind(website_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: BTreeMap<Option<String>, Vec<RollupRow>> = BTreeMap::new();
+    for row in &rows {
+        let rollup = RollupRow::from_row(row);
+        groups.entry(rollup.key.clone()).or_default().push(rollup);
+    }
+
+    let max_latency = groups
+        .values()
+        .flatten()
+        .filter_map(|rollup| rollup.latency_max)
+        .fold(0.0, f64::max);
+    let width = max_latency / buckets as f64;
+
+    Ok(groups
+        .into_iter()
+        .map(|(key, rollups)| {
+            let mut sketch = LatencySketch::default();
+            let mut latency_sum = 0.0;
+            let mut samples = 0;
+            for rollup in &rollups {
+                sketch.merge(&rollup.sketch);
+                latency_sum += rollup.latency_sum;
+                samples += rollup.up_count;
+            }
+
+            let mut histogram: Vec<LatencyBucket> = (0..buckets)
+                .map(|i| LatencyBucket {
+                    lower: width * i as f64,
+                    upper: width * (i + 1) as f64,
+                    count: 0,
+                })
+                .collect();
+            if samples > 0 {
+                for (value, count) in sketch.values() {
+                    let index = if width > 0.0 { (value / width) as usize } else { 0 };
+                    histogram[index.min(buckets as usize - 1)].count += count;
+                }
+            } else {
+                histogram.clear();
+            }
+
+            let min = rollups.iter().filter_map(|rollup| rollup.latency_min).reduce(f64::min);
+            let max = rollups.iter().filter_map(|rollup| rollup.latency_max).reduce(f64::max);
+            // Sketch values are bucket midpoints and can land just outside the observed range
+            let quantile = |q: f64| {
+                sketch
+                    .quantile(q)
+                    .map(|value| value.clamp(min.unwrap_or(value), max.unwrap_or(value)))
+            };
+
+            LatencyStats {
+                key,
+                samples,
+                min,
+                max,
+                mean: (samples > 0).then(|| latency_sum / samples as f64),
+                p50: quantile(0.5),
+                p90: quantile(0.9),
+                p95: quantile(0.95),
+                p99: quantile(0.99),
+                histogram,
+            }
+        })
+        .filter(|stats| stats.samples > 0)
+        .collect())
+}
+
+/// Latency percentiles and histogram of one website. The window defaults to
+/// the last 24 hours and is widened to whole rollup buckets;
+/// `split=validator|location` breaks the numbers down.
+pub(crate) async fn get_latency_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Query(query): Query<LatencyQuery>,
+) -> ApiJsonResponse {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query
+        .from
+        .unwrap_or_else(|| to - chrono::Duration::hours(DEFAULT_WINDOW_HOURS));
+    if from >= to {
+        return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("from must be before to"));
+    }
+    let buckets = query.buckets.unwrap_or(DEFAULT_BUCKETS).clamp(1, MAX_BUCKETS);
+
+    let website = sqlx::query("SELECT id FROM websites WHERE id = $1 AND user_id = $2 AND disabled = false")
+        .bind(website_id)
+        .bind(query.user_id)
+        .fetch_optional(&pool)
+        .await;
+
+    match website {
+        Ok(Some(_)) => {}
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Website not found")),
+        Err(e) => {
+            println!("Error fetching website: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to compute latency"),
+            );
+        }
+    }
+
+    let (table, from, to) = rollup_window(from.naive_utc(), to.naive_utc());
+    match latency_stats(&pool, website_id, table, from, to, query.split, buckets).await {
+        Ok(stats) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({
+                    "websiteId": website_id,
+                    "from": from,
+                    "to": to,
+                    "stats": stats
+                }),
+                None,
+            ),
+        ),
+        Err(e) => {
+            println!("Error computing latency: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to compute latency"),
+            )
+        }
+    }
+}
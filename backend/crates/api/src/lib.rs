@@ -1,3 +1,26 @@
 pub mod routes;
 pub mod models;
 pub mod auth;
+pub mod heartbeat;
+pub mod groups;
+pub mod ticks;
+pub mod uptime;
+pub mod maintenance;
+pub mod latency;
+pub mod rollups;
+pub mod retention;
+pub mod incidents;
+pub mod consensus;
+pub mod reputation;
+pub mod validators;
+pub mod regions;
+pub mod geoip;
+pub mod ingest;
+pub mod verification;
+pub mod gateway;
+pub mod scheduler;
+pub mod liveness;
+pub mod payouts;
+pub mod batch_payouts;
+pub mod payout_scheduler;
+pub mod csv;
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use config::get_config;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Presence older than this is no longer needed for uptime.
+const PRESENCE_RETENTION_DAYS: i64 = 7;
+
+/// Share of the last 24 hours a validator was present, counted from its
+/// first heartbeat in that window so new validators aren't penalised. `v` is
+/// the validators table and `$1` now; NULL without any heartbeat.
+pub(crate) const UPTIME_SQL: &str = "(
+    SELECT CASE WHEN COUNT(*) > 0 THEN
+        LEAST(1.0, COUNT(*)::FLOAT8 / (FLOOR(EXTRACT(EPOCH FROM (date_trunc('minute', $1) - MIN(p.minute))) / 60) + 1))
+    END
+    FROM validator_presence p
+    WHERE p.validator_id = v.id AND p.minute >= $1 - INTERVAL '24 hours'
+)";
+
+/// Marks a validator online and present in the current minute.
+pub async fn record_heartbeat(pool: &PgPool, validator_id: Uuid) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    sqlx::query("UPDATE validators SET online = true, last_seen_at = $2 WHERE id = $1")
+        .bind(validator_id)
+        .bind(now)
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "INSERT INTO validator_presence (validator_id, minute) VALUES ($1, date_trunc('minute', $2))
+         ON CONFLICT (validator_id, minute) DO NOTHING",
+    )
+    .bind(validator_id)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn mark_offline(pool: &PgPool, validator_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE validators SET online = false WHERE id = $1")
+        .bind(validator_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Marks validators whose heartbeats stopped as offline, e.g. when the hub
+/// they were connected to went away, and forgets old presence.
+pub async fn run_liveness_job(pool: PgPool) {
+    let config = get_config();
+    let offline_after = ChronoDuration::seconds(config.validator_offline_after_secs);
+    let mut interval = tokio::time::interval(Duration::from_secs(30));
+    loop {
+        interval.tick().await;
+        let now = Utc::now().naive_utc();
+        let expired = sqlx::query("UPDATE validators SET online = false WHERE online AND (last_seen_at IS NULL OR last_seen_at < $1)")
+            .bind(now - offline_after)
+            .execute(&pool)
+            .await;
+        if let Err(e) = expired {
+            println!("Liveness job error: {:?}", e);
+            continue;
+        }
+        let pruned = sqlx::query("DELETE FROM validator_presence WHERE minute < $1")
+            .bind(now - ChronoDuration::days(PRESENCE_RETENTION_DAYS))
+            .execute(&pool)
+            .await;
+        if let Err(e) = pruned {
+            println!("Liveness job error: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::NaiveDateTime;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{MaintenanceCreate, UserIdQuery},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+pub(crate) async fn create_maintenance_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Json(payload): Json<MaintenanceCreate>,
+) -> ApiJsonResponse {
+    if payload.ends_at <= payload.starts_at {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("ends_at must be after starts_at"),
+        );
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO maintenance_windows (website_id, starts_at, ends_at, reason)
+         SELECT id, $3, $4, $5 FROM websites
+         WHERE id = $1 AND user_id = $2 AND disabled = false
+         RETURNING id",
+    )
+    .bind(website_id)
+    .bind(payload.user_id)
+    .bind(payload.starts_at.naive_utc())
+    .bind(payload.ends_at.naive_utc())
+    .bind(&payload.reason)
+    .fetch_optional(&pool)
+    .await;
+
+    match result {
+        Ok(Some(row)) => ApiJsonResponse(
+            StatusCode::CREATED,
+            json_success(
+                json!({
+                    "id": row.get::<Uuid, _>("id"),
+                    "websiteId": website_id,
+                    "startsAt": payload.starts_at.naive_utc(),
+                    "endsAt": payload.ends_at.naive_utc(),
+                    "reason": payload.reason
+                }),
+                Some("Maintenance window created successfully".to_string()),
+            ),
+        ),
+        Ok(None) => ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Website not found")),
+        Err(e) => {
+            println!("Error creating maintenance window: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to create maintenance window"),
+            )
+        }
+    }
+}
+
+pub(crate) async fn get_maintenance_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Query(query): Query<UserIdQuery>,
+) -> ApiJsonResponse {
+    let result = sqlx::query(
+        "SELECT mw.id, mw.starts_at, mw.ends_at, mw.reason
+         FROM maintenance_windows mw
+         JOIN websites w ON w.id = mw.website_id
+         WHERE mw.website_id = $1 AND w.user_id = $2
+         ORDER BY mw.starts_at DESC",
+    )
+    .bind(website_id)
+    .bind(query.user_id)
+    .fetch_all(&pool)
+    .await;
+
+    match result {
+        Ok(rows) => {
+            let windows = rows
+                .iter()
+                .map(|row| {
+                    json!({
+                        "id": row.get::<Uuid, _>("id"),
+                        "websiteId": website_id,
+                        "startsAt": row.get::<NaiveDateTime, _>("starts_at"),
+                        "endsAt": row.get::<NaiveDateTime, _>("ends_at"),
+                        "reason": row.get::<Option<String>, _>("reason")
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            ApiJsonResponse(StatusCode::OK, json_success(json!({"maintenance": windows}), None))
+        }
+        Err(e) => {
+            println!("Error fetching maintenance windows: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch maintenance windows"),
+            )
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use protocol::tick::{ErrorClass, TickReport};
 use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
@@ -24,6 +26,9 @@ pub struct Website {
     #[serde(deserialize_with = "deserialize_uuid")]
     pub user_id: Uuid,
     pub disabled: bool,
+    /// Seconds between checks; 60 when not given.
+    #[serde(default)]
+    pub check_interval_secs: Option<i32>,
 }
 
 fn deserialize_uuid<'de, D>(deserializer: D) -> Result<Uuid, D::Error>
@@ -36,7 +41,11 @@ where
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WebsiteQuery{
     pub id: Uuid,
-    pub user_id: Uuid
+    pub user_id: Uuid,
+    /// Only include ticks from validators in this location.
+    pub region: Option<String>,
+    pub ticks_limit: Option<i64>,
+    pub ticks_since_minutes: Option<i64>,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct User{
@@ -55,4 +64,644 @@ pub struct UserRegister{
     pub password: String,
     pub email: String,
     pub id: Option<Uuid>
-}
\ No newline at end of file
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeartbeatCreate {
+    pub user_id: Uuid,
+    pub period_seconds: i32,
+    pub grace_seconds: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebsiteListQuery {
+    pub user_id: Uuid,
+    pub tag: Option<String>,
+    pub group_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub url: Option<String>,
+    pub sort: Option<WebsiteSort>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    pub ticks_limit: Option<i64>,
+    pub ticks_since_minutes: Option<i64>,
+    pub ticks_region: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebsiteSort {
+    Url,
+    Status,
+    Latency,
+}
+
+impl WebsiteSort {
+    /// Sort key column of the listing query, paired with the cast applied to
+    /// the cursor key, which always travels as text.
+    pub fn key_column(&self) -> (&'static str, &'static str) {
+        match self {
+            WebsiteSort::Url => ("url", "TEXT"),
+            WebsiteSort::Status => ("status_rank", "INT4"),
+            WebsiteSort::Latency => ("latency_rank", "FLOAT8"),
+        }
+    }
+}
+
+/// Position after the last website of a page. Handed to clients as an opaque
+/// base58 string.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebsiteCursor {
+    pub sort: WebsiteSort,
+    pub key: String,
+    pub id: Uuid,
+}
+
+impl WebsiteCursor {
+    pub fn encode(&self) -> String {
+        bs58::encode(serde_json::to_vec(self).unwrap_or_default()).into_string()
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = bs58::decode(cursor).into_vec().ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebsiteTags {
+    pub user_id: Uuid,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebsiteTagQuery {
+    pub user_id: Uuid,
+    pub tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupCreate {
+    pub user_id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupAssign {
+    pub user_id: Uuid,
+    pub group_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckIntervalUpdate {
+    pub user_id: Uuid,
+    pub check_interval_secs: i32,
+}
+
+/// Where the time of a check went, in milliseconds.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TickTimings {
+    pub dns_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub ttfb_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Tick {
+    pub id: Uuid,
+    pub website_id: Uuid,
+    pub validator_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub status: String,
+    pub latency: f64,
+    pub http_status_code: Option<i32>,
+    pub error_class: Option<ErrorClass>,
+    pub response_size_bytes: Option<i64>,
+    #[sqlx(flatten)]
+    pub timings: TickTimings,
+}
+
+/// Columns of `website_ticks` that make up a `Tick`.
+pub const TICK_COLUMNS: &str = "id, website_id, validator_id, created_at, status, latency, http_status_code, \
+     error_class, response_size_bytes, dns_ms, connect_ms, tls_ms, ttfb_ms";
+
+/// Body of `POST /api/ticks`: one report or a batch of them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TickSubmission {
+    Batch { ticks: Vec<TickReport> },
+    Single(Box<TickReport>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TickQuery {
+    pub user_id: Uuid,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub validator_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub region: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Position after the last tick of a page, newest first. Opaque base58 like
+/// `WebsiteCursor`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TickCursor {
+    pub created_at: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl TickCursor {
+    pub fn encode(&self) -> String {
+        bs58::encode(serde_json::to_vec(self).unwrap_or_default()).into_string()
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = bs58::decode(cursor).into_vec().ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UptimeQuery {
+    pub user_id: Uuid,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UptimeStats {
+    pub window: String,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    /// `None` when the window holds no ticks at all.
+    pub uptime_percentage: Option<f64>,
+    pub up_ticks: i64,
+    pub down_ticks: i64,
+    pub maintenance_seconds: f64,
+    /// Share of the non-maintenance time that has at least one tick per minute.
+    pub coverage: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceCreate {
+    pub user_id: Uuid,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencySplit {
+    Validator,
+    Location,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LatencyQuery {
+    pub user_id: Uuid,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub split: Option<LatencySplit>,
+    pub buckets: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    /// Validator id or location the stats are split by, `None` for the whole website.
+    pub key: Option<String>,
+    pub samples: i64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p95: Option<f64>,
+    pub p99: Option<f64>,
+    pub histogram: Vec<LatencyBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionOverride {
+    pub tick_retention_days: Option<i32>,
+    pub rollup_retention_days: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionTableReport {
+    pub table: String,
+    pub total_bytes: i64,
+    pub estimated_rows: i64,
+    /// Rows the next pruning pass would delete.
+    pub prunable_rows: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    pub id: Uuid,
+    pub website_id: Uuid,
+    pub url: String,
+    pub started_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub duration_seconds: Option<i32>,
+    pub first_failing_validators: Vec<Uuid>,
+    pub error_reasons: Vec<String>,
+}
+
+/// Latest tick and recent uptime of a website as seen from one location.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionStatus {
+    pub location: String,
+    pub status: String,
+    pub latency: f64,
+    pub last_checked_at: NaiveDateTime,
+    pub uptime: f64,
+    pub validators: i64,
+}
+
+/// Consensus verdict of one check round, see `website_status_rounds`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusRound {
+    pub round_start: NaiveDateTime,
+    pub status: String,
+    pub up_votes: i32,
+    pub down_votes: i32,
+    pub locations: i32,
+    pub latency: Option<f64>,
+    pub failing_validators: Vec<Uuid>,
+    pub error_reasons: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IncidentQuery {
+    pub user_id: Uuid,
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Where a validator is in onboarding. Only active validators are sent
+/// checks and credited for their reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum ValidatorStatus {
+    Pending,
+    Active,
+    Suspended,
+    Banned,
+}
+
+impl ValidatorStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidatorStatus::Pending => "pending",
+            ValidatorStatus::Active => "active",
+            ValidatorStatus::Suspended => "suspended",
+            ValidatorStatus::Banned => "banned",
+        }
+    }
+
+    /// Whether an admin may move a validator from this status to `next`.
+    /// Bans are final.
+    pub fn can_become(self, next: ValidatorStatus) -> bool {
+        use ValidatorStatus::*;
+        matches!(
+            (self, next),
+            (Pending | Suspended, Active) | (Pending | Active, Suspended) | (Pending | Active | Suspended, Banned)
+        )
+    }
+}
+
+/// Body of the admin routes that change a validator's status.
+#[derive(Debug, Default, Deserialize)]
+pub struct ValidatorStatusUpdate {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorSummary {
+    pub id: Uuid,
+    pub public_key: String,
+    pub location: String,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub asn: Option<i64>,
+    pub as_organization: Option<String>,
+    pub reputation: f64,
+    pub status: ValidatorStatus,
+    pub online: bool,
+    pub last_seen_at: Option<NaiveDateTime>,
+    /// Share of the last 24 hours the validator was online.
+    pub uptime: Option<f64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorDetail {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub summary: ValidatorSummary,
+    pub pending_payouts: i32,
+    pub status_reason: Option<String>,
+    pub status_changed_at: Option<NaiveDateTime>,
+}
+
+/// What a validator did over the last 24 hours.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorStats {
+    pub checks_performed: i64,
+    /// Share of the checks sent by the scheduler that it answered; `None`
+    /// when it was sent none.
+    pub response_rate: Option<f64>,
+    pub average_latency: Option<f64>,
+}
+
+/// Where a payout is: `created` before its transfer is signed, `submitted`
+/// once the signed transfer is recorded and sent, then `confirmed` or
+/// `failed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum PayoutStatus {
+    Created,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+impl PayoutStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PayoutStatus::Created => "created",
+            PayoutStatus::Submitted => "submitted",
+            PayoutStatus::Confirmed => "confirmed",
+            PayoutStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A row of the `payouts` ledger.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Payout {
+    pub id: Uuid,
+    pub validator_id: Uuid,
+    /// The batch that pays it, if any.
+    pub batch_id: Option<Uuid>,
+    /// Pending payouts it took from the validator.
+    pub credits: i32,
+    /// Lamports transferred.
+    pub amount: i64,
+    /// Credits not transferred because of reputation weighting.
+    pub forfeited: i64,
+    pub status: PayoutStatus,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub submitted_at: Option<NaiveDateTime>,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PayoutBatchCreate {
+    /// Defaults to `PAYOUT_BATCH_THRESHOLD`.
+    pub threshold: Option<i32>,
+    /// Lamports the batch may transfer in all; no cap by default.
+    pub spend_cap: Option<i64>,
+}
+
+/// A batch payout and how its payouts fared so far.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutBatchReport {
+    pub id: Uuid,
+    pub threshold: i32,
+    pub spend_cap: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+    pub recipients: i64,
+    pub transactions: i64,
+    pub confirmed: i64,
+    /// Created or submitted, and not resolved yet.
+    pub in_flight: i64,
+    pub failed: i64,
+    /// Lamports of all its payouts.
+    pub amount: i64,
+    /// Lamports of its confirmed payouts.
+    pub paid_out: i64,
+}
+
+/// How a scheduled payout run ended: it `sent` a batch, was `idle` because
+/// nobody was owed the threshold, found payouts `paused`, or `failed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum PayoutRunStatus {
+    Sent,
+    Idle,
+    Paused,
+    Failed,
+}
+
+impl PayoutRunStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PayoutRunStatus::Sent => "sent",
+            PayoutRunStatus::Idle => "idle",
+            PayoutRunStatus::Paused => "paused",
+            PayoutRunStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A row of `payout_runs`, the report the payout scheduler writes each run.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PayoutRun {
+    pub id: Uuid,
+    pub status: PayoutRunStatus,
+    pub threshold: i32,
+    pub spend_cap: i64,
+    pub batch_id: Option<Uuid>,
+    /// Validators owed the threshold but left for a later run by the cap.
+    pub deferred: i32,
+    pub error: Option<String>,
+    pub started_at: NaiveDateTime,
+    pub finished_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayoutRunQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PayoutPause {
+    pub reason: Option<String>,
+}
+
+/// How an operator route answers: the usual JSON, or a CSV download.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayoutHistoryQuery {
+    pub status: Option<PayoutStatus>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Position after the last payout of a page, newest first. Opaque base58
+/// like `WebsiteCursor`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayoutCursor {
+    pub created_at: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl PayoutCursor {
+    pub fn encode(&self) -> String {
+        bs58::encode(serde_json::to_vec(self).unwrap_or_default()).into_string()
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = bs58::decode(cursor).into_vec().ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EarningsPeriod {
+    #[default]
+    Day,
+    Month,
+}
+
+impl EarningsPeriod {
+    /// Unit for `date_trunc`.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            EarningsPeriod::Day => "day",
+            EarningsPeriod::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EarningsQuery {
+    #[serde(default)]
+    pub period: EarningsPeriod,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// What a validator earned and was paid in one day or month, in lamports.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EarningsBucket {
+    pub period_start: NaiveDateTime,
+    pub ticks: i64,
+    /// Credited for accepted ticks.
+    pub earned: i64,
+    pub payouts: i64,
+    /// Transferred by confirmed payouts, after any reputation weighting.
+    pub paid_out: i64,
+    /// Credits confirmed payouts didn't transfer because of reputation
+    /// weighting.
+    pub forfeited: i64,
+}
+
+/// One scoring run, see `validator_reputation_history`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationEntry {
+    pub computed_at: NaiveDateTime,
+    pub score: f64,
+    pub agreement: f64,
+    pub responsiveness: f64,
+    pub latency_plausibility: f64,
+    pub expected_rounds: i32,
+    pub answered_rounds: i32,
+    pub agreed: i32,
+    pub disagreed: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReputationHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+/// A connected validator as the scheduler sees it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledValidator {
+    pub validator_id: Uuid,
+    pub location: Option<String>,
+    pub reputation: f64,
+    /// Checks sent to it that it has not answered yet.
+    pub load: usize,
+}
+
+/// A validator connected to a hub that asks for a round.
+#[derive(Debug, Deserialize)]
+pub struct HubValidator {
+    pub validator_id: Uuid,
+    /// Checks the hub sent it that it has not answered yet.
+    #[serde(default)]
+    pub load: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HubRoundRequest {
+    /// Connected validators that can run HTTP checks.
+    pub validators: Vec<HubValidator>,
+}
+
+/// A website's place in the check schedule and its latest assignment.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledWebsite {
+    pub id: Uuid,
+    pub url: String,
+    pub check_interval_secs: i32,
+    pub next_check_at: NaiveDateTime,
+    pub last_round_id: Option<Uuid>,
+    pub last_assigned_at: Option<NaiveDateTime>,
+    pub last_validator_ids: Vec<Uuid>,
+}
@@ -0,0 +1,413 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use config::{get_config, Config};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    batch_payouts::{batch_reports, estimate_batch, minimum_threshold, run_batch_payout, BatchRun},
+    models::{PayoutPause, PayoutRun, PayoutRunQuery, PayoutRunStatus, ValidatorStatus},
+    payouts::payout_amount,
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+/// How often the scheduler checks whether a run is due.
+const POLL_SECS: u64 = 60;
+/// How soon a failed run is retried, unless runs are more frequent.
+const FAILED_RUN_RETRY_SECS: u64 = 900;
+
+const DEFAULT_RUNS_LIMIT: i64 = 30;
+const MAX_RUNS_LIMIT: i64 = 500;
+
+const PAYOUT_RUN_COLUMNS: &str = "id, status, threshold, spend_cap, batch_id, deferred, error, started_at, finished_at";
+
+async fn last_run(pool: &PgPool) -> Result<Option<PayoutRun>, sqlx::Error> {
+    sqlx::query_as::<_, PayoutRun>(&format!(
+        "SELECT {} FROM payout_runs ORDER BY started_at DESC LIMIT 1",
+        PAYOUT_RUN_COLUMNS
+    ))
+    .fetch_optional(pool)
+    .await
+}
+
+/// When the scheduler runs next: `PAYOUT_INTERVAL_SECS` after the last run,
+/// `FAILED_RUN_RETRY_SECS` after it if it failed, or right away if it never
+/// ran. `None` when the scheduler is disabled.
+fn next_run_at(config: &Config, last_run: Option<&PayoutRun>, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if !config.payout_scheduler_enabled {
+        return None;
+    }
+    Some(match last_run {
+        Some(run) => {
+            let delay = match run.status {
+                PayoutRunStatus::Failed => config.payout_interval_secs.min(FAILED_RUN_RETRY_SECS),
+                _ => config.payout_interval_secs,
+            };
+            run.started_at + ChronoDuration::seconds(delay as i64)
+        }
+        None => now,
+    })
+}
+
+/// Pays every validator owed `PAYOUT_BATCH_THRESHOLD` in one batch capped
+/// at `PAYOUT_RUN_SPEND_CAP`, and records the run in `payout_runs`.
+async fn run_scheduled_payout(pool: &PgPool, config: &Config) -> Result<PayoutRun, sqlx::Error> {
+    let started_at = Utc::now().naive_utc();
+    let minimum = match minimum_threshold() {
+        Ok(minimum) if (config.payout_batch_threshold as i64) < minimum as i64 => Err(format!(
+            "PAYOUT_BATCH_THRESHOLD must be at least the rent-exempt minimum of {} lamports",
+            minimum
+        )),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    let (status, batch_id, deferred, error) = match minimum {
+        Err(e) => (PayoutRunStatus::Failed, None, 0, Some(e)),
+        Ok(()) => match run_batch_payout(pool, config.payout_batch_threshold, Some(config.payout_run_spend_cap)).await {
+            Ok(BatchRun::Paused) => (PayoutRunStatus::Paused, None, 0, None),
+            Ok(BatchRun::Ran { batch: Some(batch), deferred }) => (PayoutRunStatus::Sent, Some(batch.id), deferred, None),
+            Ok(BatchRun::Ran { batch: None, deferred }) => (PayoutRunStatus::Idle, None, deferred, None),
+            Err(e) => (PayoutRunStatus::Failed, e.batch_id, 0, Some(e.error.to_string())),
+        },
+    };
+
+    sqlx::query_as::<_, PayoutRun>(&format!(
+        "INSERT INTO payout_runs (status, threshold, spend_cap, batch_id, deferred, error, started_at, finished_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {}",
+        PAYOUT_RUN_COLUMNS
+    ))
+    .bind(status.as_str())
+    .bind(config.payout_batch_threshold)
+    .bind(config.payout_run_spend_cap)
+    .bind(batch_id)
+    .bind(deferred)
+    .bind(error)
+    .bind(started_at)
+    .bind(Utc::now().naive_utc())
+    .fetch_one(pool)
+    .await
+}
+
+/// Runs a scheduled payout every `PAYOUT_INTERVAL_SECS`. The schedule
+/// follows the recorded runs, so restarting the backend doesn't pay early.
+pub async fn run_payout_scheduler(pool: PgPool) {
+    let config = get_config();
+    if !config.payout_scheduler_enabled {
+        return;
+    }
+    println!(
+        "Payout scheduler: every {}s, threshold {} lamports, cap {} lamports per run",
+        config.payout_interval_secs, config.payout_batch_threshold, config.payout_run_spend_cap
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_SECS.min(config.payout_interval_secs.max(1))));
+    loop {
+        interval.tick().await;
+        let now = Utc::now().naive_utc();
+        let due = match last_run(&pool).await {
+            Ok(last_run) => next_run_at(&config, last_run.as_ref(), now).is_some_and(|next_run_at| next_run_at <= now),
+            Err(e) => {
+                println!("Payout scheduler error: {:?}", e);
+                continue;
+            }
+        };
+        if !due {
+            continue;
+        }
+        match run_scheduled_payout(&pool, &config).await {
+            Ok(run) => match run.error {
+                Some(e) => println!("Payout scheduler error: {}", e),
+                None => println!("Payout scheduler: run {} {}", run.id, run.status.as_str()),
+            },
+            Err(e) => println!("Payout scheduler error: {:?}", e),
+        }
+    }
+}
+
+/// `GET /api/admin/payouts/schedule`: the scheduler's settings, the kill
+/// switch, the last run and what the next run would pay.
+pub(crate) async fn get_payout_schedule_handler(Extension(pool): Extension<PgPool>) -> ApiJsonResponse {
+    let config = get_config();
+    let now = Utc::now().naive_utc();
+
+    let result = async {
+        let settings = sqlx::query("SELECT paused, paused_reason, changed_at FROM payout_settings")
+            .fetch_one(&pool)
+            .await?;
+        let last_run = last_run(&pool).await?;
+        let plan = estimate_batch(&pool, config.payout_batch_threshold, Some(config.payout_run_spend_cap)).await?;
+        Ok::<_, sqlx::Error>((settings, last_run, plan))
+    }
+    .await;
+
+    match result {
+        Ok((settings, last_run, plan)) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({
+                    "enabled": config.payout_scheduler_enabled,
+                    "intervalSecs": config.payout_interval_secs,
+                    "threshold": config.payout_batch_threshold,
+                    "spendCap": config.payout_run_spend_cap,
+                    "paused": settings.get::<bool, _>("paused"),
+                    "pausedReason": settings.get::<Option<String>, _>("paused_reason"),
+                    "changedAt": settings.get::<Option<NaiveDateTime>, _>("changed_at"),
+                    "lastRun": last_run,
+                    "nextRunAt": next_run_at(&config, last_run.as_ref(), now),
+                    "upcoming": {
+                        "recipients": plan.recipients.len(),
+                        "amount": plan.amount(),
+                        "deferred": plan.deferred.len()
+                    }
+                }),
+                None,
+            ),
+        ),
+        Err(e) => {
+            println!("Error fetching payout schedule: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch payout schedule"),
+            )
+        }
+    }
+}
+
+async fn set_paused(pool: &PgPool, paused: bool, reason: Option<&str>) -> ApiJsonResponse {
+    let changed_at = Utc::now().naive_utc();
+    let result = sqlx::query("UPDATE payout_settings SET paused = $1, paused_reason = $2, changed_at = $3")
+        .bind(paused)
+        .bind(reason)
+        .bind(changed_at)
+        .execute(pool)
+        .await;
+
+    match result {
+        Ok(_) => {
+            let message = if paused { "Payouts paused" } else { "Payouts resumed" };
+            ApiJsonResponse(
+                StatusCode::OK,
+                json_success(
+                    json!({"paused": paused, "pausedReason": reason, "changedAt": changed_at}),
+                    Some(message.to_string()),
+                ),
+            )
+        }
+        Err(e) => {
+            println!("Error updating payout settings: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to update payout settings"),
+            )
+        }
+    }
+}
+
+/// `POST /api/admin/payouts/pause`: the kill switch. Stops scheduled, batch
+/// and requested payouts until resumed; payouts already claimed still go
+/// through.
+pub(crate) async fn pause_payouts_handler(
+    Extension(pool): Extension<PgPool>,
+    payload: Option<Json<PayoutPause>>,
+) -> ApiJsonResponse {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let reason = payload.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    set_paused(&pool, true, reason).await
+}
+
+/// `POST /api/admin/payouts/resume`
+pub(crate) async fn resume_payouts_handler(Extension(pool): Extension<PgPool>) -> ApiJsonResponse {
+    set_paused(&pool, false, None).await
+}
+
+/// `GET /api/admin/payouts/runs`: the latest scheduled runs with the report
+/// of the batch each sent.
+pub(crate) async fn get_payout_runs_handler(
+    Extension(pool): Extension<PgPool>,
+    Query(query): Query<PayoutRunQuery>,
+) -> ApiJsonResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_RUNS_LIMIT).clamp(1, MAX_RUNS_LIMIT);
+
+    let result = async {
+        let runs = sqlx::query_as::<_, PayoutRun>(&format!(
+            "SELECT {} FROM payout_runs ORDER BY started_at DESC LIMIT $1",
+            PAYOUT_RUN_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(&pool)
+        .await?;
+        let batch_ids: Vec<Uuid> = runs.iter().filter_map(|run| run.batch_id).collect();
+        let batches = batch_reports(&pool, &batch_ids).await?;
+        Ok::<_, sqlx::Error>((runs, batches))
+    }
+    .await;
+
+    match result {
+        Ok((runs, mut batches)) => {
+            let runs: Vec<_> = runs
+                .into_iter()
+                .map(|run| {
+                    let batch = run
+                        .batch_id
+                        .and_then(|batch_id| batches.iter().position(|batch| batch.id == batch_id))
+                        .map(|index| batches.swap_remove(index));
+                    json!({"run": run, "batch": batch})
+                })
+                .collect();
+            ApiJsonResponse(StatusCode::OK, json_success(json!({"runs": runs}), None))
+        }
+        Err(e) => {
+            println!("Error fetching payout runs: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch payout runs"),
+            )
+        }
+    }
+}
+
+/// `GET /api/validators/:id/payouts/upcoming`: what the next scheduled run
+/// would pay the validator, and why it would be skipped if it would be.
+pub(crate) async fn get_upcoming_payout_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+) -> ApiJsonResponse {
+    let config = get_config();
+    let now = Utc::now().naive_utc();
+
+    let result = async {
+        let validator = sqlx::query(
+            "SELECT v.status, v.pending_payouts, v.reputation,
+                    EXISTS (
+                        SELECT 1 FROM payouts p WHERE p.validator_id = v.id AND p.status IN ('created', 'submitted')
+                    ) AS in_flight,
+                    (SELECT paused FROM payout_settings) AS paused
+             FROM validators v
+             WHERE v.id = $1",
+        )
+        .bind(validator_id)
+        .fetch_optional(&pool)
+        .await?;
+        let Some(validator) = validator else {
+            return Ok(None);
+        };
+        let last_run = last_run(&pool).await?;
+        let plan = estimate_batch(&pool, config.payout_batch_threshold, Some(config.payout_run_spend_cap)).await?;
+        Ok::<_, sqlx::Error>(Some((validator, last_run, plan)))
+    }
+    .await;
+
+    let (validator, last_run, plan) = match result {
+        Ok(Some(found)) => found,
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Validator not found")),
+        Err(e) => {
+            println!("Error estimating upcoming payout: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to estimate upcoming payout"),
+            );
+        }
+    };
+
+    let pending_payouts = validator.get::<i32, _>("pending_payouts");
+    let amount = payout_amount(pending_payouts, validator.get::<f64, _>("reputation"));
+    let paused = validator.get::<bool, _>("paused");
+    let included = plan.recipients.iter().any(|candidate| candidate.validator_id == validator_id);
+    let skipped_because = if included {
+        None
+    } else if validator.get::<ValidatorStatus, _>("status") == ValidatorStatus::Banned {
+        Some("Validator is banned")
+    } else if validator.get::<bool, _>("in_flight") {
+        Some("A payout is in flight")
+    } else if plan.deferred.iter().any(|candidate| candidate.validator_id == validator_id) {
+        Some("Over the spending cap of this run")
+    } else {
+        Some("Below the payout threshold")
+    };
+
+    ApiJsonResponse(
+        StatusCode::OK,
+        json_success(
+            json!({
+                "scheduled": config.payout_scheduler_enabled && !paused,
+                "paused": paused,
+                "nextRunAt": next_run_at(&config, last_run.as_ref(), now),
+                "threshold": config.payout_batch_threshold,
+                "pendingPayouts": pending_payouts,
+                "estimatedAmount": amount,
+                "included": included,
+                "skippedBecause": skipped_because
+            }),
+            None,
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn config(enabled: bool, interval_secs: u64) -> Config {
+        Config {
+            payout_scheduler_enabled: enabled,
+            payout_interval_secs: interval_secs,
+            ..get_config()
+        }
+    }
+
+    fn run(status: PayoutRunStatus, started_at: NaiveDateTime) -> PayoutRun {
+        PayoutRun {
+            id: Uuid::new_v4(),
+            status,
+            threshold: 1_000_000,
+            spend_cap: 10_000_000_000,
+            batch_id: None,
+            deferred: 0,
+            error: None,
+            started_at,
+            finished_at: started_at,
+        }
+    }
+
+    fn at(hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn never_runs_when_disabled() {
+        let last_run = run(PayoutRunStatus::Failed, at(9, 0));
+        assert_eq!(next_run_at(&config(false, 3600), None, at(12, 0)), None);
+        assert_eq!(next_run_at(&config(false, 3600), Some(&last_run), at(12, 0)), None);
+    }
+
+    #[test]
+    fn runs_right_away_the_first_time() {
+        assert_eq!(next_run_at(&config(true, 86400), None, at(12, 0)), Some(at(12, 0)));
+    }
+
+    #[test]
+    fn runs_an_interval_after_the_last_run() {
+        for status in [PayoutRunStatus::Sent, PayoutRunStatus::Idle, PayoutRunStatus::Paused] {
+            let last_run = run(status, at(9, 0));
+            assert_eq!(next_run_at(&config(true, 3600), Some(&last_run), at(9, 30)), Some(at(10, 0)));
+        }
+    }
+
+    #[test]
+    fn retries_a_failed_run_sooner() {
+        let last_run = run(PayoutRunStatus::Failed, at(9, 0));
+        assert_eq!(next_run_at(&config(true, 86400), Some(&last_run), at(9, 5)), Some(at(9, 15)));
+        // Runs more frequent than the retry are not delayed by it
+        assert_eq!(next_run_at(&config(true, 300), Some(&last_run), at(9, 1)), Some(at(9, 5)));
+    }
+}
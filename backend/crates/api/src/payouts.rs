@@ -0,0 +1,755 @@
+use std::{env, str::FromStr, time::Duration};
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::{Duration as ChronoDuration, Months, Utc};
+use config::get_config;
+use serde_json::json;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction,
+    transaction::Transaction as SolanaTransaction,
+};
+use sqlx::{PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    csv::{csv_response, optional},
+    models::{
+        EarningsBucket, EarningsPeriod, EarningsQuery, ExportFormat, Payout, PayoutCursor, PayoutHistoryQuery,
+        PayoutStatus, ValidatorStatus,
+    },
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+/// A payout still `created` after this was abandoned before its transfer
+/// was signed, so nothing was sent.
+const ABANDONED_AFTER_SECS: i64 = 300;
+/// Payouts of a batch that is still running wait for their transaction
+/// for up to this long.
+const ABANDONED_BATCH_AFTER_SECS: i64 = 3600;
+/// A `submitted` payout is left to the transfer that is still sending it,
+/// and waiting for it to confirm, for this long.
+const IN_FLIGHT_AFTER_SECS: i64 = 120;
+
+const DEFAULT_PAYOUTS_PAGE_SIZE: i64 = 100;
+const MAX_PAYOUTS_PAGE_SIZE: i64 = 1000;
+
+/// Longest range of daily earnings.
+const MAX_EARNINGS_DAYS: i64 = 366;
+
+pub(crate) const PAYOUT_COLUMNS: &str =
+    "id, validator_id, batch_id, credits, amount, forfeited, status, signature, error, created_at, submitted_at, resolved_at";
+
+/// Why a payout was not made.
+#[derive(Debug)]
+pub enum PayoutError {
+    UnknownValidator,
+    Banned,
+    Paused,
+    NothingToPay(&'static str),
+    /// The transfer could not be prepared; nothing was sent and the credits
+    /// are back with the validator.
+    Transfer(String),
+    Database(sqlx::Error),
+}
+
+impl PayoutError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PayoutError::UnknownValidator => StatusCode::NOT_FOUND,
+            PayoutError::Banned => StatusCode::FORBIDDEN,
+            PayoutError::Paused => StatusCode::SERVICE_UNAVAILABLE,
+            PayoutError::NothingToPay(_) => StatusCode::BAD_REQUEST,
+            PayoutError::Transfer(_) | PayoutError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            PayoutError::UnknownValidator => "Validator not found".to_string(),
+            PayoutError::Banned => "Validator is banned".to_string(),
+            PayoutError::Paused => "Payouts are paused".to_string(),
+            PayoutError::NothingToPay(message) => message.to_string(),
+            PayoutError::Transfer(e) => e.clone(),
+            PayoutError::Database(_) => "Failed to pay out".to_string(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for PayoutError {
+    fn from(e: sqlx::Error) -> Self {
+        PayoutError::Database(e)
+    }
+}
+
+/// Whether the payout kill switch is on. The switch is locked for the rest
+/// of the transaction, so pausing waits for payouts being claimed.
+pub(crate) async fn payouts_paused(conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT paused FROM payout_settings FOR SHARE")
+        .fetch_one(conn)
+        .await
+}
+
+pub(crate) fn rpc_client() -> Result<RpcClient, String> {
+    let rpc_url = env::var("SOLANA_RPC_URL").map_err(|_| "SOLANA_RPC_URL not set".to_string())?;
+    Ok(RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()))
+}
+
+pub(crate) fn treasury_keypair() -> Result<Keypair, String> {
+    let private_key = env::var("PRIVATE_KEY").map_err(|_| "PRIVATE_KEY not set".to_string())?;
+    let private_key_bytes = bs58::decode(private_key)
+        .into_vec()
+        .map_err(|e| format!("Failed to decode base58 private key: {}", e))?;
+    Keypair::try_from(private_key_bytes.as_slice()).map_err(|e| format!("Invalid private key bytes: {}", e))
+}
+
+/// Builds and signs a transfer of `lamports` from the treasury to `pubkey`
+/// without sending it. Returns it with the last block height it can land at.
+fn sign_transfer(client: &RpcClient, pubkey: &str, lamports: i64) -> Result<(SolanaTransaction, u64), String> {
+    if lamports <= 0 {
+        return Err("Amount must be positive".to_string());
+    }
+    let lamports = lamports as u64;
+
+    let keypair = treasury_keypair()?;
+    let destination_pubkey = Pubkey::from_str(pubkey).map_err(|e| format!("Invalid destination pubkey: {}", e))?;
+
+    let balance = client
+        .get_balance(&keypair.pubkey())
+        .map_err(|e| format!("Failed to fetch balance: {}", e))?;
+    if balance < lamports {
+        return Err(format!(
+            "Insufficient balance. Needed: {:.9} SOL, Available: {:.9} SOL",
+            lamports as f64 / 1_000_000_000.0,
+            balance as f64 / 1_000_000_000.0
+        ));
+    }
+
+    let instruction = system_instruction::transfer(&keypair.pubkey(), &destination_pubkey, lamports);
+    let (recent_blockhash, last_valid_block_height) = client
+        .get_latest_blockhash_with_commitment(client.commitment())
+        .map_err(|e| format!("Failed to get blockhash: {}", e))?;
+    let transaction = SolanaTransaction::new_signed_with_payer(
+        &[instruction],
+        Some(&keypair.pubkey()),
+        &[&keypair],
+        recent_blockhash,
+    );
+    Ok((transaction, last_valid_block_height))
+}
+
+/// Lamports paid for `credits` of pending payouts. Pending payouts count
+/// ticks at the flat rate; optionally they are scaled by reputation, and the
+/// rest is recorded as the payout's `forfeited` credits.
+pub(crate) fn payout_amount(credits: i32, reputation: f64) -> i64 {
+    if get_config().payout_reputation_weighting {
+        (credits as f64 * reputation.clamp(0.0, 1.0)).round() as i64
+    } else {
+        credits as i64
+    }
+}
+
+/// Moves the validator's pending payouts into a new `created` payout, or
+/// returns the payout already in flight for it so retries don't pay twice.
+/// Also returns the validator's public key.
+async fn claim_payout(pool: &PgPool, validator_id: Uuid) -> Result<(Payout, String), PayoutError> {
+    let mut tx = pool.begin().await?;
+    if payouts_paused(&mut tx).await? {
+        return Err(PayoutError::Paused);
+    }
+
+    // Lock the row so pending payouts are claimed once
+    let validator = sqlx::query(
+        "SELECT public_key, status, pending_payouts, reputation FROM validators WHERE id = $1 FOR UPDATE",
+    )
+    .bind(validator_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(PayoutError::UnknownValidator)?;
+    if validator.get::<ValidatorStatus, _>("status") == ValidatorStatus::Banned {
+        return Err(PayoutError::Banned);
+    }
+    let public_key = validator.get::<String, _>("public_key");
+
+    let open = sqlx::query_as::<_, Payout>(&format!(
+        "SELECT {} FROM payouts WHERE validator_id = $1 AND status IN ('created', 'submitted')",
+        PAYOUT_COLUMNS
+    ))
+    .bind(validator_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(payout) = open {
+        return Ok((payout, public_key));
+    }
+
+    let credits = validator.get::<i32, _>("pending_payouts");
+    if credits <= 0 {
+        return Err(PayoutError::NothingToPay("No pending payouts"));
+    }
+    let amount = payout_amount(credits, validator.get::<f64, _>("reputation"));
+    if amount <= 0 {
+        return Err(PayoutError::NothingToPay("No payout after reputation weighting"));
+    }
+
+    let payout = sqlx::query_as::<_, Payout>(&format!(
+        "INSERT INTO payouts (validator_id, credits, amount, status, created_at)
+         VALUES ($1, $2, $3, 'created', $4)
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(validator_id)
+    .bind(credits)
+    .bind(amount)
+    .bind(Utc::now().naive_utc())
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE validators SET pending_payouts = pending_payouts - $2 WHERE id = $1")
+        .bind(validator_id)
+        .bind(credits)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok((payout, public_key))
+}
+
+async fn load_payout(pool: &PgPool, payout_id: Uuid) -> Result<Payout, sqlx::Error> {
+    sqlx::query_as::<_, Payout>(&format!("SELECT {} FROM payouts WHERE id = $1", PAYOUT_COLUMNS))
+        .bind(payout_id)
+        .fetch_one(pool)
+        .await
+}
+
+pub(crate) async fn confirm_payout(pool: &PgPool, payout_id: Uuid) -> Result<Payout, sqlx::Error> {
+    let confirmed = sqlx::query_as::<_, Payout>(&format!(
+        "UPDATE payouts SET status = 'confirmed', resolved_at = $2
+         WHERE id = $1 AND status = 'submitted'
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(payout_id)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(pool)
+    .await?;
+    match confirmed {
+        Some(payout) => Ok(payout),
+        None => load_payout(pool, payout_id).await,
+    }
+}
+
+/// Marks a payout that is still in flight as failed and gives its credits
+/// back to the validator.
+pub(crate) async fn fail_payout(pool: &PgPool, payout_id: Uuid, error: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let failed = sqlx::query(
+        "UPDATE payouts SET status = 'failed', error = $2, resolved_at = $3
+         WHERE id = $1 AND status IN ('created', 'submitted')
+         RETURNING validator_id, credits",
+    )
+    .bind(payout_id)
+    .bind(error)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(row) = failed {
+        sqlx::query("UPDATE validators SET pending_payouts = pending_payouts + $2 WHERE id = $1")
+            .bind(row.get::<Uuid, _>("validator_id"))
+            .bind(row.get::<i32, _>("credits"))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// Signs the transfer of a `created` payout, records its signature before
+/// sending it, then waits for it to confirm. A transfer that isn't
+/// confirmed in time stays `submitted` for the reconciler.
+async fn submit_payout(pool: &PgPool, payout: Payout, public_key: &str) -> Result<Payout, PayoutError> {
+    let signed = rpc_client().and_then(|client| {
+        let (transaction, last_valid_block_height) = sign_transfer(&client, public_key, payout.amount)?;
+        Ok((client, transaction, last_valid_block_height))
+    });
+    let (client, transaction, last_valid_block_height) = match signed {
+        Ok(signed) => signed,
+        Err(e) => {
+            fail_payout(pool, payout.id, &e).await?;
+            return Err(PayoutError::Transfer(e));
+        }
+    };
+
+    let submitted = sqlx::query_as::<_, Payout>(&format!(
+        "UPDATE payouts SET status = 'submitted', signature = $2, recent_blockhash = $3, last_valid_block_height = $4,
+                            submitted_at = $5
+         WHERE id = $1 AND status = 'created'
+         RETURNING {}",
+        PAYOUT_COLUMNS
+    ))
+    .bind(payout.id)
+    .bind(transaction.signatures[0].to_string())
+    .bind(transaction.message.recent_blockhash.to_string())
+    .bind(last_valid_block_height as i64)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(pool)
+    .await?;
+    let Some(payout) = submitted else {
+        // A concurrent retry or the reconciler got to it first
+        return Ok(load_payout(pool, payout.id).await?);
+    };
+
+    match client.send_and_confirm_transaction(&transaction) {
+        Ok(signature) => {
+            println!(
+                "Sent {:.9} SOL ({} lamports) to {}: {}",
+                payout.amount as f64 / 1_000_000_000.0,
+                payout.amount,
+                public_key,
+                signature
+            );
+            Ok(confirm_payout(pool, payout.id).await?)
+        }
+        Err(e) => {
+            println!("Payout {} not confirmed yet: {}", payout.id, e);
+            Ok(payout)
+        }
+    }
+}
+
+/// `POST /api/validators/:id/payouts`. Pays out the validator's pending
+/// payouts, or reports on the payout already in flight for it.
+pub(crate) async fn validator_payout_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+) -> ApiJsonResponse {
+    let result = match claim_payout(&pool, validator_id).await {
+        // Payouts of a batch are sent by the batch
+        Ok((payout, public_key)) if payout.status == PayoutStatus::Created && payout.batch_id.is_none() => {
+            submit_payout(&pool, payout, &public_key).await
+        }
+        Ok((payout, _)) => Ok(payout),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(payout) => {
+            let (status_code, message) = match payout.status {
+                PayoutStatus::Confirmed => (StatusCode::OK, "Payout confirmed"),
+                PayoutStatus::Failed => (StatusCode::BAD_GATEWAY, "Payout failed"),
+                PayoutStatus::Created | PayoutStatus::Submitted => {
+                    (StatusCode::ACCEPTED, "Payout submitted, waiting for confirmation")
+                }
+            };
+            ApiJsonResponse(status_code, json_success(json!({"payout": payout}), Some(message.to_string())))
+        }
+        Err(e) => {
+            match &e {
+                PayoutError::Database(e) => println!("Error paying out validator: {:?}", e),
+                PayoutError::Transfer(e) => println!("Error paying out validator: {}", e),
+                _ => {}
+            }
+            ApiJsonResponse(e.status_code(), json_error(&e.message()))
+        }
+    }
+}
+
+/// `GET /api/validators/:id/payouts`: a validator's payouts, newest first,
+/// paged like ticks, or all of them as CSV with `format=csv`.
+pub(crate) async fn get_payouts_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+    Query(query): Query<PayoutHistoryQuery>,
+) -> Response {
+    let csv = query.format == ExportFormat::Csv;
+    let cursor = match query.cursor.as_deref().filter(|_| !csv).map(PayoutCursor::decode) {
+        None => None,
+        Some(Some(cursor)) => Some(cursor),
+        Some(None) => {
+            return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("Invalid cursor")).into_response();
+        }
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_PAYOUTS_PAGE_SIZE).clamp(1, MAX_PAYOUTS_PAGE_SIZE);
+
+    let result = sqlx::query_as::<_, Payout>(&format!(
+        "SELECT {}
+         FROM payouts
+         WHERE validator_id = $1
+           AND ($2::TEXT IS NULL OR status = $2)
+           AND ($3::TIMESTAMP IS NULL OR created_at >= $3)
+           AND ($4::TIMESTAMP IS NULL OR created_at < $4)
+           AND ($5::TIMESTAMP IS NULL OR (created_at, id) < ($5, $6))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $7",
+        PAYOUT_COLUMNS
+    ))
+    .bind(validator_id)
+    .bind(query.status)
+    .bind(query.from.map(|from| from.naive_utc()))
+    .bind(query.to.map(|to| to.naive_utc()))
+    .bind(cursor.as_ref().map(|cursor| cursor.created_at))
+    .bind(cursor.as_ref().map(|cursor| cursor.id))
+    .bind(if csv { None } else { Some(limit + 1) })
+    .fetch_all(&pool)
+    .await;
+
+    let mut payouts = match result {
+        Ok(payouts) => payouts,
+        Err(e) => {
+            println!("Error fetching payouts: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch payouts"),
+            )
+            .into_response();
+        }
+    };
+
+    if csv {
+        return csv_response(
+            &format!("payouts-{}.csv", validator_id),
+            &[
+                "id", "createdAt", "status", "credits", "amount", "forfeited", "signature", "submittedAt", "resolvedAt",
+                "error",
+            ],
+            payouts.iter().map(|payout| {
+                vec![
+                    payout.id.to_string(),
+                    payout.created_at.to_string(),
+                    payout.status.as_str().to_string(),
+                    payout.credits.to_string(),
+                    payout.amount.to_string(),
+                    payout.forfeited.to_string(),
+                    optional(payout.signature.as_ref()),
+                    optional(payout.submitted_at),
+                    optional(payout.resolved_at),
+                    optional(payout.error.as_ref()),
+                ]
+            }),
+        );
+    }
+
+    let has_more = payouts.len() as i64 > limit;
+    payouts.truncate(limit as usize);
+    let next_cursor = payouts
+        .last()
+        .filter(|_| has_more)
+        .map(|payout| PayoutCursor { created_at: payout.created_at, id: payout.id }.encode());
+    ApiJsonResponse(
+        StatusCode::OK,
+        json_success(json!({"payouts": payouts, "nextCursor": next_cursor}), None),
+    )
+    .into_response()
+}
+
+/// `GET /api/validators/:id/earnings`: lamports a validator earned from
+/// ticks and was paid out per day or month, every period in the range
+/// included, together with what it has yet to be paid. Defaults to the last
+/// 30 days, or the last 12 months by month.
+pub(crate) async fn get_earnings_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+    Query(query): Query<EarningsQuery>,
+) -> Response {
+    let to = query.to.map(|to| to.naive_utc()).unwrap_or_else(|| Utc::now().naive_utc());
+    let from = query.from.map(|from| from.naive_utc()).unwrap_or_else(|| match query.period {
+        EarningsPeriod::Day => to - ChronoDuration::days(29),
+        EarningsPeriod::Month => to.checked_sub_months(Months::new(11)).unwrap_or(to),
+    });
+    if from > to {
+        return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("from must not be after to")).into_response();
+    }
+    if query.period == EarningsPeriod::Day && to - from > ChronoDuration::days(MAX_EARNINGS_DAYS) {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("Daily earnings cover at most 366 days, use period=month"),
+        )
+        .into_response();
+    }
+
+    let balance = sqlx::query(
+        "SELECT v.pending_payouts,
+                COALESCE((SELECT SUM(p.credits) FROM payouts p
+                          WHERE p.validator_id = v.id AND p.status IN ('created', 'submitted')), 0)::BIGINT AS in_flight
+         FROM validators v
+         WHERE v.id = $1",
+    )
+    .bind(validator_id)
+    .fetch_one(&pool)
+    .await;
+    let buckets = sqlx::query_as::<_, EarningsBucket>(
+        "WITH periods AS (
+             SELECT generate_series(date_trunc($2, $3), date_trunc($2, $4), ('1 ' || $2)::INTERVAL) AS period_start
+         ), earned AS (
+             SELECT date_trunc($2, day::TIMESTAMP) AS period_start, SUM(ticks) AS ticks, SUM(credits) AS earned
+             FROM validator_earnings_daily
+             WHERE validator_id = $1 AND day >= date_trunc($2, $3)::DATE AND day <= $4::DATE
+             GROUP BY 1
+         ), paid AS (
+             SELECT date_trunc($2, resolved_at) AS period_start, COUNT(*) AS payouts, SUM(amount) AS paid_out,
+                    SUM(forfeited) AS forfeited
+             FROM payouts
+             WHERE validator_id = $1 AND status = 'confirmed' AND resolved_at >= date_trunc($2, $3) AND resolved_at <= $4
+             GROUP BY 1
+         )
+         SELECT periods.period_start,
+                COALESCE(earned.ticks, 0)::BIGINT AS ticks,
+                COALESCE(earned.earned, 0)::BIGINT AS earned,
+                COALESCE(paid.payouts, 0)::BIGINT AS payouts,
+                COALESCE(paid.paid_out, 0)::BIGINT AS paid_out,
+                COALESCE(paid.forfeited, 0)::BIGINT AS forfeited
+         FROM periods
+         LEFT JOIN earned ON earned.period_start = periods.period_start
+         LEFT JOIN paid ON paid.period_start = periods.period_start
+         ORDER BY periods.period_start",
+    )
+    .bind(validator_id)
+    .bind(query.period.unit())
+    .bind(from)
+    .bind(to)
+    .fetch_all(&pool)
+    .await;
+
+    let (balance, buckets) = match (balance, buckets) {
+        (Ok(balance), Ok(buckets)) => (balance, buckets),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("Error fetching earnings: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch earnings"),
+            )
+            .into_response();
+        }
+    };
+
+    if query.format == ExportFormat::Csv {
+        return csv_response(
+            &format!("earnings-{}-{}.csv", validator_id, query.period.unit()),
+            &["periodStart", "ticks", "earned", "payouts", "paidOut", "forfeited"],
+            buckets.iter().map(|bucket| {
+                vec![
+                    bucket.period_start.date().to_string(),
+                    bucket.ticks.to_string(),
+                    bucket.earned.to_string(),
+                    bucket.payouts.to_string(),
+                    bucket.paid_out.to_string(),
+                    bucket.forfeited.to_string(),
+                ]
+            }),
+        );
+    }
+
+    ApiJsonResponse(
+        StatusCode::OK,
+        json_success(
+            json!({
+                "validatorId": validator_id,
+                "period": query.period,
+                "from": from,
+                "to": to,
+                "pendingPayouts": balance.get::<i32, _>("pending_payouts"),
+                "inFlight": balance.get::<i64, _>("in_flight"),
+                "totals": {
+                    "ticks": buckets.iter().map(|bucket| bucket.ticks).sum::<i64>(),
+                    "earned": buckets.iter().map(|bucket| bucket.earned).sum::<i64>(),
+                    "paidOut": buckets.iter().map(|bucket| bucket.paid_out).sum::<i64>(),
+                    "forfeited": buckets.iter().map(|bucket| bucket.forfeited).sum::<i64>()
+                },
+                "buckets": buckets
+            }),
+            None,
+        ),
+    )
+    .into_response()
+}
+
+/// The status of a transfer's signature.
+#[derive(Debug)]
+enum SignatureStatus {
+    /// Seen, but not yet at the client's commitment.
+    Processed,
+    Landed,
+    Failed(String),
+}
+
+/// Whether a transfer whose blockhash is valid up to
+/// `last_valid_block_height` can no longer land. Only the finalized chain
+/// counts: a blockhash taken at `confirmed` is younger than it for a while.
+fn expired(finalized_block_height: u64, last_valid_block_height: u64) -> bool {
+    finalized_block_height > last_valid_block_height
+}
+
+/// What became of a submitted transfer: `Some(Ok)` when it landed,
+/// `Some(Err)` when it failed or can no longer land, `None` while that is
+/// not known yet.
+fn decide_transfer(status: Option<SignatureStatus>, expired: bool) -> Option<Result<(), String>> {
+    match status {
+        Some(SignatureStatus::Processed) => None,
+        Some(SignatureStatus::Landed) => Some(Ok(())),
+        Some(SignatureStatus::Failed(e)) => Some(Err(format!("Transfer failed: {}", e))),
+        None if expired => Some(Err("Transfer expired before it landed".to_string())),
+        None => None,
+    }
+}
+
+/// Looks up a submitted transfer, see `decide_transfer`. Transfers submitted
+/// before block heights were recorded expire with their blockhash.
+fn transfer_outcome(
+    client: &RpcClient,
+    signature: &str,
+    recent_blockhash: &str,
+    last_valid_block_height: Option<u64>,
+) -> Result<Option<Result<(), String>>, String> {
+    let signature = Signature::from_str(signature).map_err(|e| format!("Invalid signature: {}", e))?;
+
+    // Expiry is checked first, so a transfer that landed in time has a
+    // status by the time it counts as expired. The status is looked up in
+    // the ledger history too: the recent status cache forgets a transfer
+    // long before it could be paid again.
+    let expired = match last_valid_block_height {
+        Some(last_valid_block_height) => expired(
+            client
+                .get_block_height_with_commitment(CommitmentConfig::finalized())
+                .map_err(|e| format!("Failed to fetch block height: {}", e))?,
+            last_valid_block_height,
+        ),
+        None => {
+            let recent_blockhash =
+                Hash::from_str(recent_blockhash).map_err(|e| format!("Invalid blockhash: {}", e))?;
+            !client
+                .is_blockhash_valid(&recent_blockhash, CommitmentConfig::finalized())
+                .map_err(|e| format!("Failed to check blockhash: {}", e))?
+        }
+    };
+    let status = client
+        .get_signature_statuses_with_history(&[signature])
+        .map_err(|e| format!("Failed to fetch signature status: {}", e))?
+        .value
+        .into_iter()
+        .next()
+        .flatten()
+        .map(|status| match status.err {
+            _ if !status.satisfies_commitment(client.commitment()) => SignatureStatus::Processed,
+            None => SignatureStatus::Landed,
+            Some(e) => SignatureStatus::Failed(e.to_string()),
+        });
+    Ok(decide_transfer(status, expired))
+}
+
+/// Resolves payouts a crash or a failed confirmation left in flight:
+/// abandoned `created` payouts fail, and `submitted` ones are confirmed or
+/// failed from the status of their transaction's signature. Failed payouts
+/// give their credits back.
+pub async fn reconcile_payouts(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let abandoned = sqlx::query_scalar::<_, Uuid>(
+        "SELECT p.id FROM payouts p
+         LEFT JOIN payout_batches b ON b.id = p.batch_id
+         WHERE p.status = 'created' AND p.created_at < $1
+           AND (b.id IS NULL OR b.completed_at IS NOT NULL OR b.created_at < $2)",
+    )
+    .bind(now - ChronoDuration::seconds(ABANDONED_AFTER_SECS))
+    .bind(now - ChronoDuration::seconds(ABANDONED_BATCH_AFTER_SECS))
+    .fetch_all(pool)
+    .await?;
+    for payout_id in abandoned {
+        fail_payout(pool, payout_id, "Abandoned before submission").await?;
+    }
+
+    // Payouts sent in one transaction share its signature and its outcome.
+    // Transfers submitted recently are still being sent and confirmed.
+    let submitted = sqlx::query(
+        "SELECT signature, recent_blockhash, last_valid_block_height, ARRAY_AGG(id) AS ids
+         FROM payouts
+         WHERE status = 'submitted'
+         GROUP BY signature, recent_blockhash, last_valid_block_height
+         HAVING MAX(submitted_at) < $1
+         ORDER BY MIN(submitted_at)",
+    )
+    .bind(now - ChronoDuration::seconds(IN_FLIGHT_AFTER_SECS))
+    .fetch_all(pool)
+    .await?;
+    if submitted.is_empty() {
+        return Ok(());
+    }
+    let client = match rpc_client() {
+        Ok(client) => client,
+        Err(e) => {
+            println!("Payout reconciler error: {}", e);
+            return Ok(());
+        }
+    };
+
+    for row in submitted {
+        let signature = row.get::<String, _>("signature");
+        let payout_ids = row.get::<Vec<Uuid>, _>("ids");
+        let last_valid_block_height = row.get::<Option<i64>, _>("last_valid_block_height");
+        match transfer_outcome(
+            &client,
+            &signature,
+            &row.get::<String, _>("recent_blockhash"),
+            last_valid_block_height.map(|height| height as u64),
+        ) {
+            Ok(Some(Ok(()))) => {
+                for payout_id in payout_ids {
+                    confirm_payout(pool, payout_id).await?;
+                }
+            }
+            Ok(Some(Err(e))) => {
+                for payout_id in payout_ids {
+                    fail_payout(pool, payout_id, &e).await?;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => println!("Error reconciling transfer {}: {}", signature, e),
+        }
+    }
+    Ok(())
+}
+
+pub async fn run_payout_reconciler(pool: PgPool) {
+    let config = get_config();
+    let mut interval = tokio::time::interval(Duration::from_secs(config.payout_reconcile_interval_secs));
+    loop {
+        interval.tick().await;
+        if let Err(e) = reconcile_payouts(&pool).await {
+            println!("Payout reconciler error: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blockhash_expires_only_once_finalized_past_its_last_valid_height() {
+        // Signed at a confirmed blockhash the finalized chain hasn't reached
+        assert!(!expired(1_000, 1_150));
+        assert!(!expired(1_150, 1_150));
+        assert!(expired(1_151, 1_150));
+    }
+
+    #[test]
+    fn unknown_transfer_fails_only_when_expired() {
+        assert!(decide_transfer(None, false).is_none());
+        assert!(matches!(decide_transfer(None, true), Some(Err(_))));
+    }
+
+    #[test]
+    fn known_transfer_follows_its_status() {
+        assert!(matches!(decide_transfer(Some(SignatureStatus::Landed), true), Some(Ok(()))));
+        assert!(matches!(
+            decide_transfer(Some(SignatureStatus::Failed("insufficient funds".to_string())), false),
+            Some(Err(_))
+        ));
+        // Seen but not confirmed may still land, or fail, on another fork
+        assert!(decide_transfer(Some(SignatureStatus::Processed), true).is_none());
+    }
+}
@@ -0,0 +1,33 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::RegionStatus;
+
+/// Window of the per-region uptime.
+const REGION_WINDOW_HOURS: i64 = 24;
+
+/// Status of a website per validator location: the latest tick from the
+/// location and the share of successful ticks in the last
+/// `REGION_WINDOW_HOURS`. Locations without ticks in the window and ticks
+/// recorded by the backend itself are left out.
+pub async fn region_breakdown(pool: &PgPool, website_id: Uuid) -> Result<Vec<RegionStatus>, sqlx::Error> {
+    let since = Utc::now().naive_utc() - ChronoDuration::hours(REGION_WINDOW_HOURS);
+    sqlx::query_as::<_, RegionStatus>(
+        "SELECT v.location,
+                CASE (array_agg(wt.status ORDER BY wt.created_at DESC))[1] WHEN 'Good' THEN 'up' ELSE 'down' END AS status,
+                (array_agg(wt.latency ORDER BY wt.created_at DESC))[1] AS latency,
+                MAX(wt.created_at) AS last_checked_at,
+                (100.0 * COUNT(*) FILTER (WHERE wt.status = 'Good') / COUNT(*))::FLOAT8 AS uptime,
+                COUNT(DISTINCT wt.validator_id) AS validators
+         FROM website_ticks wt
+         JOIN validators v ON v.id = wt.validator_id
+         WHERE wt.website_id = $1 AND wt.created_at >= $2
+         GROUP BY v.location
+         ORDER BY v.location",
+    )
+    .bind(website_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use monitor_core::reputation::{score, ReputationInputs};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::consensus::{consensus_watermark, TICK_ROUND_JOIN, TICK_ROUND_START};
+
+const REPUTATION_INTERVAL_SECS: u64 = 3600;
+/// Reputation reflects the validator's behaviour over this many hours of
+/// decided rounds.
+const REPUTATION_WINDOW_HOURS: i64 = 24;
+/// A successful report is implausible when its latency is off from the round
+/// median by more than this factor, or not positive.
+const LATENCY_TOLERANCE_FACTOR: f64 = 10.0;
+
+/// Scores every validator that reported or was assigned checks in the window
+/// ending at the consensus watermark, updates `validators.reputation` and appends to the
+/// history. Returns the number of validators scored.
+pub async fn score_validators(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let Some(to) = consensus_watermark(pool).await? else {
+        return Ok(0);
+    };
+    let from = to - ChronoDuration::hours(REPUTATION_WINDOW_HOURS);
+
+    // Expected rounds are the checks assigned to the validator, answered ones
+    // those it reported in for the same round; reports count towards
+    // agreement only against up or down verdicts.
+    let rows = sqlx::query(&format!(
+        "WITH votes AS (
+             SELECT wt.validator_id, wt.website_id, {start} AS round_start, wt.status, wt.latency
+             FROM website_ticks wt
+             {join}
+             WHERE wt.validator_id IS NOT NULL AND wt.created_at >= $1 AND wt.created_at < $2
+         ),
+         assigned AS (
+             SELECT a.validator_id, COUNT(*) AS expected_rounds, COUNT(wt.id) AS answered_rounds
+             FROM check_assignments a
+             LEFT JOIN website_ticks wt ON wt.validator_id = a.validator_id
+                 AND wt.website_id = a.website_id AND wt.round_id = a.round_id
+             WHERE a.assigned_at >= $1 AND a.assigned_at < $2
+             GROUP BY a.validator_id
+         ),
+         judged AS (
+             SELECT v.validator_id,
+                    COUNT(*) FILTER (WHERE r.status IN ('up', 'down')
+                        AND (v.status = 'Good') = (r.status = 'up')) AS agreed,
+                    COUNT(*) FILTER (WHERE r.status IN ('up', 'down')
+                        AND (v.status = 'Good') <> (r.status = 'up')) AS disagreed,
+                    COUNT(*) FILTER (WHERE v.status = 'Good' AND r.latency IS NOT NULL) AS latency_samples,
+                    COUNT(*) FILTER (WHERE v.status = 'Good' AND r.latency IS NOT NULL
+                        AND (v.latency <= 0 OR v.latency > r.latency * $3 OR v.latency * $3 < r.latency)
+                    ) AS implausible_latencies
+             FROM votes v
+             JOIN website_status_rounds r ON r.website_id = v.website_id AND r.round_start = v.round_start
+             GROUP BY v.validator_id
+         ),
+         scored AS (
+             SELECT validator_id FROM votes
+             UNION
+             SELECT validator_id FROM assigned
+         )
+         SELECT s.validator_id,
+                COALESCE(a.expected_rounds, 0) AS expected_rounds,
+                COALESCE(a.answered_rounds, 0) AS answered_rounds,
+                COALESCE(j.agreed, 0) AS agreed,
+                COALESCE(j.disagreed, 0) AS disagreed,
+                COALESCE(j.latency_samples, 0) AS latency_samples,
+                COALESCE(j.implausible_latencies, 0) AS implausible_latencies
+         FROM scored s
+         LEFT JOIN assigned a ON a.validator_id = s.validator_id
+         LEFT JOIN judged j ON j.validator_id = s.validator_id",
+        start = TICK_ROUND_START,
+        join = TICK_ROUND_JOIN,
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(LATENCY_TOLERANCE_FACTOR)
+    .fetch_all(pool)
+    .await?;
+
+    let computed_at = Utc::now().naive_utc();
+    let mut tx = pool.begin().await?;
+    for row in &rows {
+        let inputs = ReputationInputs {
+            expected_rounds: row.get::<i64, _>("expected_rounds") as u64,
+            answered_rounds: row.get::<i64, _>("answered_rounds") as u64,
+            agreed: row.get::<i64, _>("agreed") as u64,
+            disagreed: row.get::<i64, _>("disagreed") as u64,
+            latency_samples: row.get::<i64, _>("latency_samples") as u64,
+            implausible_latencies: row.get::<i64, _>("implausible_latencies") as u64,
+        };
+        let reputation = score(&inputs);
+        let validator_id = row.get::<Uuid, _>("validator_id");
+
+        sqlx::query("UPDATE validators SET reputation = $2 WHERE id = $1")
+            .bind(validator_id)
+            .bind(reputation.score)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO validator_reputation_history
+                 (validator_id, computed_at, score, agreement, responsiveness, latency_plausibility,
+                  expected_rounds, answered_rounds, agreed, disagreed)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(validator_id)
+        .bind(computed_at)
+        .bind(reputation.score)
+        .bind(reputation.agreement)
+        .bind(reputation.responsiveness)
+        .bind(reputation.latency_plausibility)
+        .bind(inputs.expected_rounds as i32)
+        .bind(inputs.answered_rounds as i32)
+        .bind(inputs.agreed as i32)
+        .bind(inputs.disagreed as i32)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(rows.len())
+}
+
+pub async fn run_reputation_job(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(REPUTATION_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        match score_validators(&pool).await {
+            Ok(0) => {}
+            Ok(scored) => println!("Reputation job: scored {} validators", scored),
+            Err(e) => println!("Reputation job error: {:?}", e),
+        }
+    }
+}
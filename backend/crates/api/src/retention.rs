@@ -0,0 +1,257 @@
+use std::time::Duration;
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{NaiveDateTime, Utc};
+use config::{get_config, Config};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{RetentionOverride, RetentionTableReport},
+    rollups::rollup_watermark,
+    routes::{json_error, json_success, ApiJsonResponse},
+    verification::prune_expired_nonces,
+};
+
+/// Pause between deletion batches so pruning never monopolises the table.
+const PRUNE_BATCH_PAUSE_MS: u64 = 100;
+
+/// A table subject to retention: its time column and the override column in
+/// `retention_overrides` that applies to it.
+struct PruneTarget {
+    table: &'static str,
+    time_column: &'static str,
+    override_column: &'static str,
+}
+
+const PRUNE_TARGETS: [PruneTarget; 5] = [
+    PruneTarget { table: "website_ticks", time_column: "created_at", override_column: "tick_retention_days" },
+    PruneTarget { table: "website_status_rounds", time_column: "round_start", override_column: "tick_retention_days" },
+    PruneTarget { table: "check_assignments", time_column: "assigned_at", override_column: "tick_retention_days" },
+    PruneTarget { table: "tick_rollups_hourly", time_column: "bucket_start", override_column: "rollup_retention_days" },
+    PruneTarget { table: "tick_rollups_daily", time_column: "bucket_start", override_column: "rollup_retention_days" },
+];
+
+impl PruneTarget {
+    fn default_days(&self, config: &Config) -> i32 {
+        if self.override_column == "tick_retention_days" {
+            config.tick_retention_days
+        } else {
+            config.rollup_retention_days
+        }
+    }
+
+    /// Rows past their owner's retention. `$1` is an upper bound on the time
+    /// column that lets the scan use the time index, `$2` is now and `$3` the
+    /// default retention in days.
+    fn expired_sql(&self) -> String {
+        format!(
+            "SELECT t.ctid FROM {table} t
+             JOIN websites w ON w.id = t.website_id
+             LEFT JOIN retention_overrides ro ON ro.user_id = w.user_id
+             WHERE t.{time} < $1
+               AND t.{time} < $2 - make_interval(days => COALESCE(ro.{days}, $3))",
+            table = self.table,
+            time = self.time_column,
+            days = self.override_column,
+        )
+    }
+
+    /// Upper bound for the time column: nothing newer than the shortest
+    /// retention in effect can expire, and raw ticks are kept until they
+    /// have been rolled up.
+    async fn cutoff(&self, pool: &PgPool, config: &Config, now: NaiveDateTime) -> Result<NaiveDateTime, sqlx::Error> {
+        let shortest_days = sqlx::query(&format!(
+            "SELECT LEAST($1, MIN({days})) AS days FROM retention_overrides",
+            days = self.override_column,
+        ))
+        .bind(self.default_days(config))
+        .fetch_one(pool)
+        .await?
+        .get::<Option<i32>, _>("days")
+        .unwrap_or(self.default_days(config));
+
+        let cutoff = now - chrono::Duration::days(shortest_days as i64);
+        if self.table == "website_ticks" {
+            let watermark = rollup_watermark(pool).await?.unwrap_or(NaiveDateTime::MIN);
+            return Ok(cutoff.min(watermark));
+        }
+        Ok(cutoff)
+    }
+}
+
+/// Deletes expired rows from every retention target in batches of
+/// `PRUNE_BATCH_SIZE`, each batch in its own short transaction, then the
+/// validator nonces that can no longer be replayed.
+pub async fn prune_expired(pool: &PgPool, config: &Config) -> Result<u64, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let mut total = 0;
+    for target in &PRUNE_TARGETS {
+        let cutoff = target.cutoff(pool, config, now).await?;
+        let sql = format!(
+            "DELETE FROM {table} WHERE ctid = ANY(ARRAY({expired} LIMIT $4))",
+            table = target.table,
+            expired = target.expired_sql(),
+        );
+        loop {
+            let deleted = sqlx::query(&sql)
+                .bind(cutoff)
+                .bind(now)
+                .bind(target.default_days(config))
+                .bind(config.prune_batch_size)
+                .execute(pool)
+                .await?
+                .rows_affected();
+            total += deleted;
+            if deleted < config.prune_batch_size as u64 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(PRUNE_BATCH_PAUSE_MS)).await;
+        }
+    }
+    total += prune_expired_nonces(pool).await?;
+    Ok(total)
+}
+
+pub async fn run_retention_job(pool: PgPool) {
+    let config = get_config();
+    let mut interval = tokio::time::interval(Duration::from_secs(config.prune_interval_secs));
+    loop {
+        interval.tick().await;
+        match prune_expired(&pool, &config).await {
+            Ok(0) => {}
+            Ok(deleted) => println!("Retention job: pruned {} rows", deleted),
+            Err(e) => println!("Retention job error: {:?}", e),
+        }
+    }
+}
+
+async fn table_report(pool: &PgPool, config: &Config, target: &PruneTarget) -> Result<RetentionTableReport, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let cutoff = target.cutoff(pool, config, now).await?;
+
+    let size = sqlx::query(
+        "SELECT pg_total_relation_size(c.oid) AS total_bytes, GREATEST(c.reltuples, 0)::BIGINT AS estimated_rows
+         FROM pg_class c
+         WHERE c.oid = to_regclass($1)",
+    )
+    .bind(target.table)
+    .fetch_one(pool)
+    .await?;
+
+    let prunable = sqlx::query(&format!("SELECT COUNT(*) AS prunable FROM ({}) expired", target.expired_sql()))
+        .bind(cutoff)
+        .bind(now)
+        .bind(target.default_days(config))
+        .fetch_one(pool)
+        .await?;
+
+    Ok(RetentionTableReport {
+        table: target.table.to_string(),
+        total_bytes: size.get::<i64, _>("total_bytes"),
+        estimated_rows: size.get::<i64, _>("estimated_rows"),
+        prunable_rows: prunable.get::<i64, _>("prunable"),
+    })
+}
+
+/// Table sizes and what the next pruning pass will remove.
+pub(crate) async fn retention_report_handler(Extension(pool): Extension<PgPool>) -> ApiJsonResponse {
+    let config = get_config();
+    let mut tables = Vec::new();
+    for target in &PRUNE_TARGETS {
+        match table_report(&pool, &config, target).await {
+            Ok(report) => tables.push(report),
+            Err(e) => {
+                println!("Error building retention report: {:?}", e);
+                return ApiJsonResponse(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    json_error("Failed to build retention report"),
+                );
+            }
+        }
+    }
+
+    let overrides = sqlx::query("SELECT COUNT(*) AS overrides FROM retention_overrides")
+        .fetch_one(&pool)
+        .await
+        .map(|row| row.get::<i64, _>("overrides"))
+        .unwrap_or(0);
+
+    ApiJsonResponse(
+        StatusCode::OK,
+        json_success(
+            json!({
+                "policy": {
+                    "tickRetentionDays": config.tick_retention_days,
+                    "rollupRetentionDays": config.rollup_retention_days,
+                    "batchSize": config.prune_batch_size,
+                    "intervalSecs": config.prune_interval_secs,
+                    "overrides": overrides
+                },
+                "tables": tables
+            }),
+            None,
+        ),
+    )
+}
+
+/// Sets or clears (both fields null) the retention override of an account.
+pub(crate) async fn set_retention_override_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<RetentionOverride>,
+) -> ApiJsonResponse {
+    let invalid = |days: Option<i32>| days.is_some_and(|days| days <= 0);
+    if invalid(payload.tick_retention_days) || invalid(payload.rollup_retention_days) {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("Retention must be a positive number of days"),
+        );
+    }
+
+    let result = if payload.tick_retention_days.is_none() && payload.rollup_retention_days.is_none() {
+        sqlx::query("DELETE FROM retention_overrides WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+    } else {
+        sqlx::query(
+            "INSERT INTO retention_overrides (user_id, tick_retention_days, rollup_retention_days)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (user_id) DO UPDATE
+             SET tick_retention_days = EXCLUDED.tick_retention_days,
+                 rollup_retention_days = EXCLUDED.rollup_retention_days",
+        )
+        .bind(user_id)
+        .bind(payload.tick_retention_days)
+        .bind(payload.rollup_retention_days)
+        .execute(&pool)
+        .await
+    };
+
+    match result {
+        Ok(_) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({
+                    "userId": user_id,
+                    "tickRetentionDays": payload.tick_retention_days,
+                    "rollupRetentionDays": payload.rollup_retention_days
+                }),
+                Some("Retention override updated".to_string()),
+            ),
+        ),
+        Err(e) => {
+            println!("Error updating retention override: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to update retention override"),
+            )
+        }
+    }
+}
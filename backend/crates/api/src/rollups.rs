@@ -0,0 +1,311 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Timelike, Utc};
+use sqlx::{types::Json, PgPool, Row};
+
+/// Relative spacing of the latency sketch buckets. Bucket `i` holds latencies
+/// in `(GAMMA^(i-1), GAMMA^i]`, so any quantile read back from the sketch is
+/// within about 2.5% of the true value.
+pub const SKETCH_GAMMA: f64 = 1.05;
+
+const ROLLUP_INTERVAL_SECS: u64 = 60;
+const ROLLUP_WATERMARK: &str = "tick_rollups";
+/// Upper bound on how much history one pass of the background job rolls up,
+/// so catching up after downtime happens in small transactions.
+const MAX_HOURS_PER_PASS: i64 = 24;
+
+/// Mergeable latency histogram with log-scale buckets, stored as the
+/// `latency_sketch` JSONB column of the rollup tables.
+#[derive(Debug, Default, Clone)]
+pub struct LatencySketch {
+    buckets: BTreeMap<i32, i64>,
+}
+
+impl LatencySketch {
+    pub fn from_json(sketch: &BTreeMap<String, i64>) -> Self {
+        let buckets = sketch
+            .iter()
+            .filter_map(|(bucket, count)| bucket.parse::<i32>().ok().map(|bucket| (bucket, *count)))
+            .collect();
+        LatencySketch { buckets }
+    }
+
+    pub fn merge(&mut self, other: &LatencySketch) {
+        for (bucket, count) in &other.buckets {
+            *self.buckets.entry(*bucket).or_insert(0) += count;
+        }
+    }
+
+    pub fn count(&self) -> i64 {
+        self.buckets.values().sum()
+    }
+
+    /// Representative latency of a bucket: the point with equal relative
+    /// error to both of its bounds.
+    fn bucket_value(bucket: i32) -> f64 {
+        if bucket <= 0 {
+            return 0.0;
+        }
+        2.0 * SKETCH_GAMMA.powi(bucket) / (SKETCH_GAMMA + 1.0)
+    }
+
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let total = self.count();
+        if total == 0 {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * (total - 1) as f64).floor() as i64;
+        let mut seen = 0;
+        for (bucket, count) in &self.buckets {
+            seen += count;
+            if seen > rank {
+                return Some(Self::bucket_value(*bucket));
+            }
+        }
+        self.buckets.keys().last().map(|bucket| Self::bucket_value(*bucket))
+    }
+
+    /// Representative latency and sample count of every non-empty bucket.
+    pub fn values(&self) -> impl Iterator<Item = (f64, i64)> + '_ {
+        self.buckets
+            .iter()
+            .map(|(bucket, count)| (Self::bucket_value(*bucket), *count))
+    }
+}
+
+pub fn truncate_to_hour(at: NaiveDateTime) -> NaiveDateTime {
+    at.date().and_hms_opt(at.hour(), 0, 0).unwrap_or(at)
+}
+
+pub fn truncate_to_day(at: NaiveDateTime) -> NaiveDateTime {
+    at.date().and_hms_opt(0, 0, 0).unwrap_or(at)
+}
+
+pub fn ceil_to_hour(at: NaiveDateTime) -> NaiveDateTime {
+    let hour = truncate_to_hour(at);
+    if hour == at { hour } else { hour + ChronoDuration::hours(1) }
+}
+
+pub fn ceil_to_day(at: NaiveDateTime) -> NaiveDateTime {
+    let day = truncate_to_day(at);
+    if day == at { day } else { day + ChronoDuration::days(1) }
+}
+
+/// Recomputes hourly rollups for every hour overlapping `[from, to)` from raw
+/// ticks. Safe to run repeatedly over the same range.
+pub async fn refresh_hourly_rollups(
+    pool: &PgPool,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<u64, sqlx::Error> {
+    let from = truncate_to_hour(from);
+    let to = ceil_to_hour(to);
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM tick_rollups_hourly WHERE bucket_start >= $1 AND bucket_start < $2")
+        .bind(from)
+        .bind(to)
+        .execute(&mut *tx)
+        .await?;
+
+    let result = sqlx::query(
+        "WITH ticks AS (
+             SELECT website_id, validator_id, date_trunc('hour', created_at) AS bucket_start,
+                    created_at, status, latency,
+                    CASE WHEN latency <= 1 THEN 0 ELSE CEIL(LN(latency) / $3)::INT END AS sketch_bucket
+             FROM website_ticks
+             WHERE created_at >= $1 AND created_at < $2
+         ),
+         sketches AS (
+             SELECT website_id, validator_id, bucket_start, jsonb_object_agg(sketch_bucket, samples) AS latency_sketch
+             FROM (
+                 SELECT website_id, validator_id, bucket_start, sketch_bucket, COUNT(*) AS samples
+                 FROM ticks
+                 WHERE status = 'Good'
+                 GROUP BY website_id, validator_id, bucket_start, sketch_bucket
+             ) per_bucket
+             GROUP BY website_id, validator_id, bucket_start
+         ),
+         website_minutes AS (
+             SELECT website_id, bucket_start, COUNT(DISTINCT date_trunc('minute', created_at)) AS active_minutes
+             FROM ticks
+             GROUP BY website_id, bucket_start
+         )
+         INSERT INTO tick_rollups_hourly
+             (website_id, validator_id, bucket_start, up_count, down_count, active_minutes,
+              latency_sum, latency_min, latency_max, latency_sketch)
+         SELECT t.website_id, t.validator_id, t.bucket_start,
+                COUNT(*) FILTER (WHERE t.status = 'Good'),
+                COUNT(*) FILTER (WHERE t.status <> 'Good'),
+                wm.active_minutes,
+                COALESCE(SUM(t.latency) FILTER (WHERE t.status = 'Good'), 0),
+                MIN(t.latency) FILTER (WHERE t.status = 'Good'),
+                MAX(t.latency) FILTER (WHERE t.status = 'Good'),
+                COALESCE(s.latency_sketch, '{}')
+         FROM ticks t
+         LEFT JOIN sketches s
+             ON s.website_id = t.website_id
+            AND s.validator_id IS NOT DISTINCT FROM t.validator_id
+            AND s.bucket_start = t.bucket_start
+         JOIN website_minutes wm ON wm.website_id = t.website_id AND wm.bucket_start = t.bucket_start
+         GROUP BY t.website_id, t.validator_id, t.bucket_start, s.latency_sketch, wm.active_minutes",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(SKETCH_GAMMA.ln())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// Recomputes daily rollups for every day overlapping `[from, to)` by merging
+/// the hourly rollups, which must already cover that range.
+pub async fn refresh_daily_rollups(
+    pool: &PgPool,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<u64, sqlx::Error> {
+    let from = truncate_to_day(from);
+    let to = ceil_to_day(to);
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM tick_rollups_daily WHERE bucket_start >= $1 AND bucket_start < $2")
+        .bind(from)
+        .bind(to)
+        .execute(&mut *tx)
+        .await?;
+
+    let result = sqlx::query(
+        "WITH sketches AS (
+             SELECT website_id, validator_id, bucket_start, jsonb_object_agg(sketch_bucket, samples) AS latency_sketch
+             FROM (
+                 SELECT h.website_id, h.validator_id, date_trunc('day', h.bucket_start) AS bucket_start,
+                        e.key AS sketch_bucket, SUM(e.value::BIGINT) AS samples
+                 FROM tick_rollups_hourly h, jsonb_each_text(h.latency_sketch) e
+                 WHERE h.bucket_start >= $1 AND h.bucket_start < $2
+                 GROUP BY h.website_id, h.validator_id, date_trunc('day', h.bucket_start), e.key
+             ) per_bucket
+             GROUP BY website_id, validator_id, bucket_start
+         )
+         INSERT INTO tick_rollups_daily
+             (website_id, validator_id, bucket_start, up_count, down_count, active_minutes,
+              latency_sum, latency_min, latency_max, latency_sketch)
+         SELECT h.website_id, h.validator_id, date_trunc('day', h.bucket_start),
+                SUM(h.up_count), SUM(h.down_count), SUM(h.active_minutes),
+                SUM(h.latency_sum), MIN(h.latency_min), MAX(h.latency_max),
+                COALESCE(s.latency_sketch, '{}')
+         FROM tick_rollups_hourly h
+         LEFT JOIN sketches s
+             ON s.website_id = h.website_id
+            AND s.validator_id IS NOT DISTINCT FROM h.validator_id
+            AND s.bucket_start = date_trunc('day', h.bucket_start)
+         WHERE h.bucket_start >= $1 AND h.bucket_start < $2
+         GROUP BY h.website_id, h.validator_id, date_trunc('day', h.bucket_start), s.latency_sketch",
+    )
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// Recomputes hourly and daily rollups for `[from, to)`, one day at a time.
+/// Days whose raw ticks were already pruned come back empty, so keep the
+/// range within the raw tick retention.
+pub async fn backfill_rollups(
+    pool: &PgPool,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<(), sqlx::Error> {
+    let mut day = truncate_to_day(from);
+    while day < to {
+        let next_day = day + ChronoDuration::days(1);
+        let hourly = refresh_hourly_rollups(pool, day, next_day).await?;
+        refresh_daily_rollups(pool, day, next_day).await?;
+        println!("Rolled up {} ({} hourly rows)", day.date(), hourly);
+        day = next_day;
+    }
+    Ok(())
+}
+
+/// Start of the first hour that has not been rolled up for good yet. Hourly
+/// rollups before it are final; later hours must be read from raw ticks.
+pub async fn rollup_watermark(pool: &PgPool) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    let row = sqlx::query("SELECT watermark FROM job_watermarks WHERE name = $1")
+        .bind(ROLLUP_WATERMARK)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| truncate_to_hour(row.get::<NaiveDateTime, _>("watermark"))))
+}
+
+async fn roll_up_pending(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let from = match rollup_watermark(pool).await? {
+        Some(watermark) => watermark,
+        None => {
+            let first_tick = sqlx::query("SELECT MIN(created_at) AS first_tick FROM website_ticks")
+                .fetch_one(pool)
+                .await?
+                .get::<Option<NaiveDateTime>, _>("first_tick");
+            match first_tick {
+                Some(first_tick) => truncate_to_hour(first_tick),
+                None => truncate_to_hour(now),
+            }
+        }
+    };
+    let to = now.min(from + ChronoDuration::hours(MAX_HOURS_PER_PASS));
+
+    refresh_hourly_rollups(pool, from, to).await?;
+    refresh_daily_rollups(pool, from, to).await?;
+
+    sqlx::query(
+        "INSERT INTO job_watermarks (name, watermark) VALUES ($1, $2)
+         ON CONFLICT (name) DO UPDATE SET watermark = EXCLUDED.watermark",
+    )
+    .bind(ROLLUP_WATERMARK)
+    .bind(to)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Keeps the rollup tables current. Each pass re-aggregates from the hour the
+/// previous pass stopped in up to now, so the open hour stays fresh too.
+pub async fn run_rollup_job(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(ROLLUP_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if let Err(e) = roll_up_pending(&pool).await {
+            println!("Rollup job error: {:?}", e);
+        }
+    }
+}
+
+/// Rollup rows as read back by the stats endpoints.
+pub(crate) struct RollupRow {
+    pub key: Option<String>,
+    pub up_count: i64,
+    pub latency_sum: f64,
+    pub latency_min: Option<f64>,
+    pub latency_max: Option<f64>,
+    pub sketch: LatencySketch,
+}
+
+impl RollupRow {
+    pub(crate) fn from_row(row: &sqlx::postgres::PgRow) -> Self {
+        let sketch = row.get::<Json<BTreeMap<String, i64>>, _>("latency_sketch");
+        RollupRow {
+            key: row.get::<Option<String>, _>("key"),
+            up_count: row.get::<i32, _>("up_count") as i64,
+            latency_sum: row.get::<f64, _>("latency_sum"),
+            latency_min: row.get::<Option<f64>, _>("latency_min"),
+            latency_max: row.get::<Option<f64>, _>("latency_max"),
+            sketch: LatencySketch::from_json(&sketch.0),
+        }
+    }
+}
@@ -1,33 +1,61 @@
-use std::{env, str::FromStr};
+use std::collections::HashMap;
 
 use axum::{
     extract::Query,
     http::StatusCode,
+    middleware,
     response::{IntoResponse, Json},
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Extension, Router,
 };
 use chrono::Utc;
 use jsonwebtoken::{EncodingKey, Header,encode};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use sqlx::{postgres::PgRow, PgPool, Postgres, Row, Transaction};
+use sqlx::{postgres::PgRow, PgPool, Row};
 use uuid::Uuid;
 use crate::{
-    auth::auth_middleware,
-    models::{Claims, User, UserIdQuery, UserRegister, Website, WebsiteQuery},
+    auth::{admin_middleware, auth_middleware, validator_auth_middleware},
+    batch_payouts::{create_payout_batch_handler, get_payout_batch_handler},
+    consensus::{current_status, recent_rounds},
+    gateway::validator_socket_handler,
+    groups::{
+        add_tags_handler, assign_group_handler, create_group_handler, delete_group_handler,
+        get_groups_handler, remove_tag_handler,
+    },
+    heartbeat::{
+        create_heartbeat_handler, heartbeat_fail_handler, heartbeat_ping_handler,
+        heartbeat_start_handler,
+    },
+    incidents::{get_incident_feed_handler, get_website_incidents_handler},
+    ingest::submit_ticks_handler,
+    latency::get_latency_handler,
+    maintenance::{create_maintenance_handler, get_maintenance_handler},
+    payout_scheduler::{
+        get_payout_runs_handler, get_payout_schedule_handler, get_upcoming_payout_handler, pause_payouts_handler,
+        resume_payouts_handler,
+    },
+    payouts::{get_earnings_handler, get_payouts_handler, validator_payout_handler},
+    models::{
+        Claims, Tick, User, UserRegister, Website, WebsiteCursor, WebsiteListQuery, WebsiteQuery,
+        WebsiteSort, TICK_COLUMNS,
+    },
+    regions::region_breakdown,
+    retention::{retention_report_handler, set_retention_override_handler},
+    scheduler::{
+        get_schedule_handler, plan_hub_round_handler, set_check_interval_handler, DEFAULT_CHECK_INTERVAL_SECS,
+        MIN_CHECK_INTERVAL_SECS,
+    },
+    ticks::get_ticks_handler,
+    uptime::{get_uptime_handler, standard_uptime_for_websites},
+    validators::{
+        approve_validator_handler, ban_validator_handler, get_reputation_history_handler, get_validator_handler,
+        get_validators_handler, suspend_validator_handler,
+    },
 };
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::{SaltString, rand_core::OsRng};
 use argon2::password_hash::Error;
-use bs58;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::{
-    pubkey::Pubkey,
-    signature::{Keypair, Signer, read_keypair_file},
-    system_instruction,
-    transaction::Transaction as SolanaTransaction
-};
 
 #[derive(Serialize)]
 struct ApiResponse<T> {
@@ -36,7 +64,7 @@ struct ApiResponse<T> {
     data: Option<T>,
 }
 
-fn json_success<T: Serialize>(data: T, message: Option<String>) -> Json<Value> {
+pub(crate) fn json_success<T: Serialize>(data: T, message: Option<String>) -> Json<Value> {
     Json(json!({
         "success": true,
         "message": message,
@@ -44,7 +72,7 @@ fn json_success<T: Serialize>(data: T, message: Option<String>) -> Json<Value> {
     }))
 }
 
-fn json_error(message: &str) -> Json<Value> {
+pub(crate) fn json_error(message: &str) -> Json<Value> {
     Json(json!({
         "success": false,
         "message": message,
@@ -52,7 +80,12 @@ fn json_error(message: &str) -> Json<Value> {
     }))
 }
 
-struct ApiJsonResponse(StatusCode, Json<Value>);
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+const DEFAULT_TICKS_PER_WEBSITE: i64 = 50;
+const MAX_TICKS_PER_WEBSITE: i64 = 500;
+
+pub(crate) struct ApiJsonResponse(pub(crate) StatusCode, pub(crate) Json<Value>);
 
 impl IntoResponse for ApiJsonResponse {
     fn into_response(self) -> axum::response::Response {
@@ -69,11 +102,19 @@ async fn createWebsite(
     Json(payload): Json<Website>,
 ) -> ApiJsonResponse {
     println!("Url: {}", payload.url);
+    let check_interval_secs = payload.check_interval_secs.unwrap_or(DEFAULT_CHECK_INTERVAL_SECS);
+    if check_interval_secs < MIN_CHECK_INTERVAL_SECS {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("Check interval must be at least 60 seconds"),
+        );
+    }
     let result = sqlx::query!(
-        "INSERT INTO websites (url, user_id, disabled) VALUES ($1, $2, $3)",
+        "INSERT INTO websites (url, user_id, disabled, check_interval_secs) VALUES ($1, $2, $3, $4)",
         payload.url,
         payload.user_id,
-        payload.disabled
+        payload.disabled,
+        check_interval_secs
     )
     .execute(&pool)
     .await;
@@ -82,7 +123,7 @@ async fn createWebsite(
         Ok(_) => ApiJsonResponse(
             StatusCode::CREATED,
             json_success(
-                json!({"url": payload.url, "user_id": payload.user_id}),
+                json!({"url": payload.url, "user_id": payload.user_id, "checkIntervalSecs": check_interval_secs}),
                 Some("Website created successfully!".to_string()),
             ),
         ),
@@ -106,7 +147,7 @@ async fn getWebsiteStatus(
 
     // First, get the website with the disabled condition
     let website_result = sqlx::query(
-        "SELECT id, url, user_id, disabled 
+        "SELECT id, url, user_id, disabled, monitor_type 
          FROM websites 
          WHERE id = $1 AND user_id = $2 AND disabled = false",
     )
@@ -117,41 +158,56 @@ async fn getWebsiteStatus(
 
     match website_result {
         Ok(Some(website_row)) => {
-            // Website found, now get its ticks
+            // Website found, now get its consensus rounds and ticks
             let website_id = website_row.get::<Uuid, _>("id");
+            let rounds = match recent_rounds(&pool, website_id).await {
+                Ok(rounds) => rounds,
+                Err(e) => {
+                    println!("Error fetching status rounds: {:?}", e);
+                    Vec::new()
+                }
+            };
+            let regions = match region_breakdown(&pool, website_id).await {
+                Ok(regions) => regions,
+                Err(e) => {
+                    println!("Error fetching region breakdown: {:?}", e);
+                    Vec::new()
+                }
+            };
 
-            // Get ticks for this website
-            let ticks_result = sqlx::query(
-                "SELECT wt.id, wt.website_id, wt.validator_id, wt.created_at, wt.status, wt.latency
-                 FROM website_ticks wt
-                 WHERE wt.website_id = $1",
-            )
+            // Get the recent ticks of this website; the full history is paged by /websites/:id/ticks
+            let ticks_limit = query.ticks_limit.unwrap_or(DEFAULT_TICKS_PER_WEBSITE).clamp(0, MAX_TICKS_PER_WEBSITE);
+            let ticks_since = query
+                .ticks_since_minutes
+                .map(|minutes| Utc::now().naive_utc() - chrono::Duration::minutes(minutes.max(0)));
+            let ticks_result = sqlx::query_as::<_, Tick>(&format!(
+                "SELECT {TICK_COLUMNS}
+                 FROM website_ticks
+                 WHERE website_id = $1
+                   AND ($2::TEXT IS NULL OR validator_id IN (SELECT id FROM validators WHERE location = $2))
+                   AND ($3::TIMESTAMP IS NULL OR created_at >= $3)
+                 ORDER BY created_at DESC
+                 LIMIT $4"
+            ))
             .bind(website_id)
+            .bind(query.region.as_deref())
+            .bind(ticks_since)
+            .bind(ticks_limit)
             .fetch_all(&pool)
             .await;
 
             match ticks_result {
-                Ok(tick_rows) => {
-                    // Convert tick rows to JSON objects
-                    let ticks = tick_rows
-                        .iter()
-                        .map(|row| {
-                            json!({
-                                "id": row.get::<Uuid, _>("id"),
-                                "websiteId": row.get::<Uuid, _>("website_id"),
-                                "validatorId": row.get::<Uuid, _>("validator_id"),
-                                "status": row.get::<String, _>("status"),
-                                "latency": row.get::<f64, _>("latency")
-                            })
-                        })
-                        .collect::<Vec<_>>();
-
+                Ok(ticks) => {
                     // Build complete response with website and ticks
                     let website_data = json!({
                         "id": website_id,
                         "url": website_row.get::<String, _>("url"),
                         "userId": website_row.get::<Uuid, _>("user_id"),
                         "disabled": website_row.get::<bool, _>("disabled"),
+                        "monitorType": website_row.get::<String, _>("monitor_type"),
+                        "currentStatus": current_status(&rounds),
+                        "rounds": rounds,
+                        "regions": regions,
                         "ticks": ticks
                     });
 
@@ -165,6 +221,10 @@ async fn getWebsiteStatus(
                         "url": website_row.get::<String, _>("url"),
                         "userId": website_row.get::<Uuid, _>("user_id"),
                         "disabled": website_row.get::<bool, _>("disabled"),
+                        "monitorType": website_row.get::<String, _>("monitor_type"),
+                        "currentStatus": current_status(&rounds),
+                        "rounds": rounds,
+                        "regions": regions,
                         "ticks": []
                     });
 
@@ -185,80 +245,174 @@ async fn getWebsiteStatus(
 
 async fn getWebsites(
     Extension(pool): Extension<PgPool>,
-    Query(query): Query<UserIdQuery>,
+    Query(query): Query<WebsiteListQuery>,
 ) -> ApiJsonResponse {
     let user_id = query.user_id;
-    let websites_result = sqlx::query(
-        "SELECT id, url, user_id, disabled 
-         FROM websites 
-         WHERE user_id = $1 AND disabled = false"
-    )
-    .bind(user_id)
-    .fetch_all(&pool)
-    .await;
-
-    match websites_result {
-        Ok(website_rows) => {
-            let mut websites_with_ticks = Vec::new();
-            
-            // For each website, fetch its ticks
-            for website_row in website_rows {
-                let website_id = website_row.get::<Uuid, _>("id");
-                let url = website_row.get::<String, _>("url");
-                let user_id = website_row.get::<Uuid, _>("user_id");
-                let disabled = website_row.get::<bool, _>("disabled");
-                
-                // Fetch ticks for this website
-                let ticks_result = sqlx::query(
-                    "SELECT id, website_id, validator_id, created_at, status, latency
-                     FROM website_ticks
-                     WHERE website_id = $1"
-                )
-                .bind(website_id)
-                .fetch_all(&pool)
-                .await;
-                
-                let ticks = match ticks_result {
-                    Ok(tick_rows) => {
-                        tick_rows.iter().map(|row| {
-                            json!({
-                                "id": row.get::<Uuid, _>("id"),
-                                "websiteId": row.get::<Uuid, _>("website_id"),
-                                "validatorId": row.get::<Uuid, _>("validator_id"),
-                                "status": row.get::<String, _>("status"),
-                                "latency": row.get::<f64, _>("latency")
-                            })
-                        }).collect::<Vec<_>>()
-                    },
-                    Err(e) => {
-                        println!("Error fetching ticks for website {}: {:?}", website_id, e);
-                        Vec::new() // Empty ticks array if there was an error
-                    }
-                };
-                
-                // Create website JSON with ticks included
-                websites_with_ticks.push(json!({
-                    "id": website_id,
-                    "url": url,
-                    "userId": user_id,
-                    "disabled": disabled,
-                    "ticks": ticks
-                }));
-            }
+    if let Some(status) = query.status.as_deref() {
+        if !matches!(status, "up" | "down" | "degraded" | "unknown") {
+            return ApiJsonResponse(
+                StatusCode::BAD_REQUEST,
+                json_error("status must be one of up, down, degraded, unknown"),
+            );
+        }
+    }
 
-            ApiJsonResponse(StatusCode::OK, json_success(
-                json!({"websites": websites_with_ticks}), 
-                None
-            ))
+    let sort = query.sort.unwrap_or(WebsiteSort::Url);
+    let cursor = match query.cursor.as_deref().map(WebsiteCursor::decode) {
+        None => None,
+        Some(Some(cursor)) if cursor.sort == sort => Some(cursor),
+        Some(_) => {
+            return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("Invalid cursor"));
         }
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let ticks_limit = query.ticks_limit.unwrap_or(DEFAULT_TICKS_PER_WEBSITE).clamp(0, MAX_TICKS_PER_WEBSITE);
+    let ticks_since = query
+        .ticks_since_minutes
+        .map(|minutes| Utc::now().naive_utc() - chrono::Duration::minutes(minutes.max(0)));
+
+    // current_status and latency come from the latest conclusive consensus
+    // round of each website. Status sorts down first, latency sorts slowest
+    // first, websites without rounds last.
+    let (key_column, key_cast) = sort.key_column();
+    let sql = format!(
+        "SELECT * FROM (
+             SELECT w.id, w.url, w.user_id, w.disabled, w.monitor_type, w.group_id,
+                    COALESCE(
+                        (SELECT array_agg(t.tag ORDER BY t.tag) FROM website_tags t WHERE t.website_id = w.id),
+                        '{{}}'
+                    ) AS tags,
+                    COALESCE(latest.status, 'unknown') AS current_status,
+                    latest.latency AS current_latency,
+                    CASE latest.status WHEN 'down' THEN 0 WHEN 'degraded' THEN 1 WHEN 'up' THEN 3 ELSE 2 END AS status_rank,
+                    -COALESCE(latest.latency, -1) AS latency_rank
+             FROM websites w
+             LEFT JOIN LATERAL (
+                 SELECT r.status, r.latency FROM website_status_rounds r
+                 WHERE r.website_id = w.id AND r.status <> 'inconclusive'
+                 ORDER BY r.round_start DESC
+                 LIMIT 1
+             ) latest ON true
+             WHERE w.user_id = $1 AND w.disabled = false
+               AND ($2::TEXT IS NULL OR EXISTS (
+                   SELECT 1 FROM website_tags t WHERE t.website_id = w.id AND t.tag = $2
+               ))
+               AND ($3::UUID IS NULL OR w.group_id = $3)
+               AND ($4::TEXT IS NULL OR strpos(lower(w.url), lower($4)) > 0)
+         ) filtered
+         WHERE ($5::TEXT IS NULL OR current_status = $5)
+           AND ($6::TEXT IS NULL OR ({key_column}, id) > ($6::{key_cast}, $7))
+         ORDER BY {key_column}, id
+         LIMIT $8"
+    );
+    let websites_result = sqlx::query(&sql)
+        .bind(user_id)
+        .bind(query.tag.as_deref().map(str::trim))
+        .bind(query.group_id)
+        .bind(query.url.as_deref())
+        .bind(query.status.as_deref())
+        .bind(cursor.as_ref().map(|cursor| cursor.key.clone()))
+        .bind(cursor.as_ref().map(|cursor| cursor.id))
+        .bind(limit + 1)
+        .fetch_all(&pool)
+        .await;
+
+    let mut website_rows = match websites_result {
+        Ok(rows) => rows,
         Err(e) => {
             println!("Error fetching websites: {:?}", e);
-            ApiJsonResponse(
+            return ApiJsonResponse(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 json_error("Failed to fetch websites"),
-            )
+            );
+        }
+    };
+
+    let has_more = website_rows.len() as i64 > limit;
+    website_rows.truncate(limit as usize);
+    let next_cursor = website_rows.last().filter(|_| has_more).map(|row| {
+        let key = match sort {
+            WebsiteSort::Url => row.get::<String, _>("url"),
+            WebsiteSort::Status => row.get::<i32, _>("status_rank").to_string(),
+            WebsiteSort::Latency => row.get::<f64, _>("latency_rank").to_string(),
+        };
+        WebsiteCursor { sort, key, id: row.get::<Uuid, _>("id") }.encode()
+    });
+
+    // Fetch the recent ticks of the whole page in one query
+    let website_ids: Vec<Uuid> = website_rows.iter().map(|row| row.get::<Uuid, _>("id")).collect();
+    let ticks_result = sqlx::query_as::<_, Tick>(&format!(
+        "SELECT t.*
+         FROM UNNEST($1::UUID[]) AS page(page_website_id)
+         CROSS JOIN LATERAL (
+             SELECT {TICK_COLUMNS}
+             FROM website_ticks
+             WHERE website_id = page.page_website_id
+               AND ($2::TIMESTAMP IS NULL OR created_at >= $2)
+               AND ($4::TEXT IS NULL OR validator_id IN (SELECT id FROM validators WHERE location = $4))
+             ORDER BY created_at DESC
+             LIMIT $3
+         ) t"
+    ))
+    .bind(&website_ids)
+    .bind(ticks_since)
+    .bind(ticks_limit)
+    .bind(query.ticks_region.as_deref())
+    .fetch_all(&pool)
+    .await;
+
+    let mut uptime_by_website = match standard_uptime_for_websites(&pool, &website_ids).await {
+        Ok(uptime) => uptime,
+        Err(e) => {
+            println!("Error computing uptime for websites: {:?}", e);
+            HashMap::new()
+        }
+    };
+
+    let mut ticks_by_website: HashMap<Uuid, Vec<Tick>> = HashMap::new();
+    match ticks_result {
+        Ok(ticks) => {
+            for tick in ticks {
+                ticks_by_website.entry(tick.website_id).or_default().push(tick);
+            }
+        }
+        Err(e) => {
+            println!("Error fetching ticks for websites: {:?}", e);
         }
     }
+
+    let websites = website_rows
+        .iter()
+        .map(|website_row| {
+            let website_id = website_row.get::<Uuid, _>("id");
+            json!({
+                "id": website_id,
+                "url": website_row.get::<String, _>("url"),
+                "userId": website_row.get::<Uuid, _>("user_id"),
+                "disabled": website_row.get::<bool, _>("disabled"),
+                "monitorType": website_row.get::<String, _>("monitor_type"),
+                "groupId": website_row.get::<Option<Uuid>, _>("group_id"),
+                "tags": website_row.get::<Vec<String>, _>("tags"),
+                "currentStatus": website_row.get::<String, _>("current_status"),
+                "currentLatency": website_row.get::<Option<f64>, _>("current_latency"),
+                "uptime": uptime_by_website
+                    .remove(&website_id)
+                    .map(|windows| {
+                        windows
+                            .into_iter()
+                            .map(|(label, stats)| (label, stats.uptime_percentage))
+                            .collect::<HashMap<_, _>>()
+                    })
+                    .unwrap_or_default(),
+                "ticks": ticks_by_website.remove(&website_id).unwrap_or_default()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    ApiJsonResponse(StatusCode::OK, json_success(
+        json!({"websites": websites, "nextCursor": next_cursor}),
+        None
+    ))
 }
 
 async fn deleteWebsite(
@@ -453,136 +607,6 @@ pub struct LoginResponse {
     pub user_id: Uuid,
 }
 
-#[derive(Deserialize)]
-pub struct ValidatorPayout {
-    validator_id: Uuid,
-}
-
-pub async fn validator_payout_handler(
-    Extension(pool): Extension<PgPool>,
-    Json(payload): Json<ValidatorPayout>,
-) -> Result<Json<String>, (axum::http::StatusCode, String)> {
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(|e| {
-        (
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to start transaction: {}", e),
-        )
-    })?;
-
-    // Lock the row to avoid race conditions (FOR UPDATE)
-    let validator = sqlx::query!(
-        r#"
-        SELECT * FROM validators WHERE id = $1 FOR UPDATE
-        "#,
-        payload.validator_id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|_| (axum::http::StatusCode::NOT_FOUND, "Validator not found".to_string()))?;
-
-    if validator.pending_payouts == 0 {
-        return Err((
-            axum::http::StatusCode::BAD_REQUEST,
-            "No pending payouts".to_string(),
-        ));
-    }
-
-    // Create Solana TX
-    let solana_signature =  send_solana_payout(&validator.public_key, validator.pending_payouts)
-        .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e))?;
-
-    // Reset the counter
-    sqlx::query!(
-        r#"
-        UPDATE validators SET pending_payouts = 0 WHERE id = $1
-        "#,
-        validator.id
-    )
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)))?;
-
-    tx.commit().await.unwrap();
-
-    Ok(Json({solana_signature}))
-}
-
-async fn send_solana_payout(pubkey: &str, lamports: i32) -> Result<String, String> {
-    if lamports <= 0 {
-        return Err("Amount must be positive".to_string());
-    }
-    
-    let lamports = lamports as u64; 
-    
-    // Get environment variables
-    let rpc_url = env::var("SOLANA_RPC_URL")
-        .map_err(|_| "SOLANA_RPC_URL not set".to_string())?;
-    let private_key = env::var("PRIVATE_KEY")
-        .map_err(|_| "PRIVATE_KEY not set".to_string())?;
-    
-    // Initialize RPC client
-    let client = RpcClient::new(rpc_url);
-    
-    // Parse private key
-    let private_key_bytes = bs58::decode(private_key)
-        .into_vec()
-        .map_err(|e| format!("Failed to decode base58 private key: {}", e))?;
-    
-    let keypair = Keypair::from_bytes(&private_key_bytes)
-        .map_err(|e| format!("Invalid private key bytes: {}", e))?;
-    
-    // Parse destination pubkey
-    let destination_pubkey = Pubkey::from_str(pubkey)
-        .map_err(|e| format!("Invalid destination pubkey: {}", e))?;
-    
-    // Check balance
-    let balance = client
-        .get_balance(&keypair.pubkey())
-        .map_err(|e| format!("Failed to fetch balance: {}", e))?;
-    
-    if balance < lamports {
-        let sol_needed = lamports as f64 / 1_000_000_000.0;
-        let sol_available = balance as f64 / 1_000_000_000.0;
-        let msg = format!(
-            "Insufficient balance. Needed: {:.9} SOL, Available: {:.9} SOL",
-            sol_needed, sol_available
-        );
-        return Err(msg);
-    }
-    
-    // Create transfer instruction
-    let instruction = system_instruction::transfer(
-        &keypair.pubkey(),
-        &destination_pubkey,
-        lamports, // Use original lamports amount, not converted SOL
-    );
-    
-    // Get recent blockhash
-    let recent_blockhash = client
-        .get_latest_blockhash()
-        .map_err(|e| format!("Failed to get blockhash: {}", e))?;
-    
-    // Create and sign transaction
-    let transaction = SolanaTransaction::new_signed_with_payer(
-        &[instruction],
-        Some(&keypair.pubkey()),
-        &[&keypair],
-        recent_blockhash,
-    );
-    
-    // Send transaction
-    let signature = client
-        .send_and_confirm_transaction(&transaction)
-        .map_err(|e| format!("Failed to send transaction: {}", e))?;
-    
-    let sol_amount = lamports as f64 / 1_000_000_000.0;
-    println!("✅ Transaction sent! Signature: {}", signature);
-    println!("💰 Sent {:.9} SOL ({} lamports) to {}", sol_amount, lamports, pubkey);
-    
-    Ok(signature.to_string())
-}
-
 pub fn routes() -> Router {
     Router::new()
         .nest(
@@ -595,7 +619,54 @@ pub fn routes() -> Router {
                 .route("/delete-website", delete(deleteWebsite))
                 .route("/sign-up", post(signup_handler))
                 .route("/login", post(login_handler))
-                .route("/validator_payout", post(validator_payout_handler))
+                .route("/create-heartbeat", post(create_heartbeat_handler))
+                .route("/heartbeat/:token", post(heartbeat_ping_handler))
+                .route("/heartbeat/:token/start", post(heartbeat_start_handler))
+                .route("/heartbeat/:token/fail", post(heartbeat_fail_handler))
+                .route("/websites/:id/tags", post(add_tags_handler).delete(remove_tag_handler))
+                .route("/websites/:id/group", put(assign_group_handler))
+                .route("/websites/:id/interval", put(set_check_interval_handler))
+                .route("/ticks", post(submit_ticks_handler))
+                .route("/websites/:id/ticks", get(get_ticks_handler))
+                .route("/websites/:id/uptime", get(get_uptime_handler))
+                .route("/websites/:id/latency", get(get_latency_handler))
+                .route("/websites/:id/incidents", get(get_website_incidents_handler))
+                .route("/incidents", get(get_incident_feed_handler))
+                .route(
+                    "/websites/:id/maintenance",
+                    get(get_maintenance_handler).post(create_maintenance_handler),
+                )
+                .route("/groups", get(get_groups_handler).post(create_group_handler))
+                .route("/groups/:id", delete(delete_group_handler))
+                .route("/validators", get(get_validators_handler))
+                .route("/validators/:id", get(get_validator_handler))
+                .route("/validators/:id/reputation", get(get_reputation_history_handler))
+                .route("/validators/ws", get(validator_socket_handler))
+                .merge(
+                    Router::new()
+                        .route("/validators/:id/payouts", get(get_payouts_handler).post(validator_payout_handler))
+                        .route("/validators/:id/payouts/upcoming", get(get_upcoming_payout_handler))
+                        .route("/validators/:id/earnings", get(get_earnings_handler))
+                        .route_layer(middleware::from_fn(validator_auth_middleware)),
+                )
+                .nest(
+                    "/admin",
+                    Router::new()
+                        .route("/retention", get(retention_report_handler))
+                        .route("/retention/:user_id", put(set_retention_override_handler))
+                        .route("/schedule", get(get_schedule_handler))
+                        .route("/schedule/round", post(plan_hub_round_handler))
+                        .route("/validators/:id/approve", post(approve_validator_handler))
+                        .route("/validators/:id/suspend", post(suspend_validator_handler))
+                        .route("/validators/:id/ban", post(ban_validator_handler))
+                        .route("/payouts/batch", post(create_payout_batch_handler))
+                        .route("/payouts/batches/:id", get(get_payout_batch_handler))
+                        .route("/payouts/schedule", get(get_payout_schedule_handler))
+                        .route("/payouts/runs", get(get_payout_runs_handler))
+                        .route("/payouts/pause", post(pause_payouts_handler))
+                        .route("/payouts/resume", post(resume_payouts_handler))
+                        .route_layer(middleware::from_fn(admin_middleware)),
+                )
                 // .layer(middleware::from_fn(auth_middleware))
         )
 }
\ No newline at end of file
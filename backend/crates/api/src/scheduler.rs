@@ -0,0 +1,319 @@
+use std::{collections::HashMap, time::Duration};
+
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use chrono::Utc;
+use config::get_config;
+use monitor_core::scheduler::{assign, next_check_at, Candidate};
+use protocol::negotiation::Capability;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    gateway::{check_website, ValidatorGateway},
+    models::{CheckIntervalUpdate, HubRoundRequest, ScheduledValidator, ScheduledWebsite},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+pub const DEFAULT_CHECK_INTERVAL_SECS: i32 = 60;
+/// One consensus round; shorter intervals would put several checks of a
+/// validator into the same round.
+pub const MIN_CHECK_INTERVAL_SECS: i32 = 60;
+
+/// A due website and the validators picked to check it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedCheck {
+    pub website_id: Uuid,
+    pub url: String,
+    pub validator_ids: Vec<Uuid>,
+}
+
+/// The active validators among `connected`, with their location,
+/// reputation and unanswered requests.
+async fn candidates(
+    pool: &PgPool,
+    connected: &[Uuid],
+    loads: &HashMap<Uuid, usize>,
+) -> Result<Vec<Candidate>, sqlx::Error> {
+    if connected.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query("SELECT id, location, reputation FROM validators WHERE id = ANY($1) AND status = 'active'")
+        .bind(connected)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let validator_id = row.get::<Uuid, _>("id");
+            Candidate {
+                validator_id,
+                location: Some(row.get::<String, _>("location")).filter(|location| location != "unknown"),
+                reputation: row.get::<f64, _>("reputation"),
+                load: loads.get(&validator_id).copied().unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+/// Validators connected to the gateway that can run HTTP checks.
+async fn gateway_candidates(pool: &PgPool, gateway: &ValidatorGateway) -> Result<Vec<Candidate>, sqlx::Error> {
+    let connected: Vec<Uuid> = gateway
+        .connected()
+        .into_iter()
+        .filter(|validator| validator.negotiated.supports(Capability::Http))
+        .map(|validator| validator.validator_id)
+        .collect();
+    candidates(pool, &connected, &gateway.loads()).await
+}
+
+/// Assigns every due website to `per_check` of `candidates` as one round,
+/// records the assignments and moves each website's `next_check_at` on.
+/// Websites stay due while no validator is connected. The gateway and each
+/// hub plan rounds over their own validators; a due website is planned by
+/// whichever asks first.
+pub async fn plan_round(
+    pool: &PgPool,
+    candidates: &[Candidate],
+    per_check: usize,
+) -> Result<Option<(Uuid, Vec<PlannedCheck>)>, sqlx::Error> {
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut tx = pool.begin().await?;
+    let due = sqlx::query(
+        "SELECT id, url, check_interval_secs, next_check_at FROM websites
+         WHERE disabled = false AND monitor_type = 'http' AND next_check_at <= $1
+         ORDER BY next_check_at
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(now)
+    .fetch_all(&mut *tx)
+    .await?;
+    if due.is_empty() {
+        return Ok(None);
+    }
+
+    let website_ids: Vec<Uuid> = due.iter().map(|row| row.get::<Uuid, _>("id")).collect();
+    let assignments = assign(&website_ids, candidates, per_check);
+    let round_id = Uuid::new_v4();
+
+    let mut checks = Vec::with_capacity(due.len());
+    for (website, assignment) in due.iter().zip(assignments) {
+        let next = next_check_at(
+            website.get("next_check_at"),
+            website.get::<i32, _>("check_interval_secs") as i64,
+            now,
+        );
+        sqlx::query("UPDATE websites SET next_check_at = $2 WHERE id = $1")
+            .bind(assignment.website_id)
+            .bind(next)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO check_assignments (round_id, website_id, validator_id, assigned_at)
+             SELECT $1, $2, validator_id, $4 FROM UNNEST($3::UUID[]) AS validator_id",
+        )
+        .bind(round_id)
+        .bind(assignment.website_id)
+        .bind(&assignment.validator_ids)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        checks.push(PlannedCheck {
+            website_id: assignment.website_id,
+            url: website.get("url"),
+            validator_ids: assignment.validator_ids,
+        });
+    }
+    tx.commit().await?;
+    Ok(Some((round_id, checks)))
+}
+
+pub async fn run_scheduler(pool: PgPool, gateway: ValidatorGateway) {
+    let config = get_config();
+    let mut interval = tokio::time::interval(Duration::from_secs(config.scheduler_poll_secs));
+    loop {
+        interval.tick().await;
+        let planned = match gateway_candidates(&pool, &gateway).await {
+            Ok(candidates) => plan_round(&pool, &candidates, config.validators_per_check).await,
+            Err(e) => Err(e),
+        };
+        let (round_id, checks) = match planned {
+            Ok(Some(plan)) => plan,
+            Ok(None) => continue,
+            Err(e) => {
+                println!("Scheduler error: {:?}", e);
+                continue;
+            }
+        };
+        for check in checks {
+            for validator_id in check.validator_ids {
+                tokio::spawn(check_website(
+                    pool.clone(),
+                    gateway.clone(),
+                    validator_id,
+                    check.website_id,
+                    check.url.clone(),
+                    round_id,
+                ));
+            }
+        }
+    }
+}
+
+/// `POST /api/admin/schedule/round`: plans a round for a hub over the
+/// validators connected to it. The hub sends each check to the validators
+/// picked for it, with the round id, and relays their reports to
+/// `/api/ticks`.
+pub(crate) async fn plan_hub_round_handler(
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<HubRoundRequest>,
+) -> ApiJsonResponse {
+    let config = get_config();
+    let connected: Vec<Uuid> = payload.validators.iter().map(|validator| validator.validator_id).collect();
+    let loads: HashMap<Uuid, usize> = payload
+        .validators
+        .iter()
+        .map(|validator| (validator.validator_id, validator.load))
+        .collect();
+
+    let planned = match candidates(&pool, &connected, &loads).await {
+        Ok(candidates) => plan_round(&pool, &candidates, config.validators_per_check).await,
+        Err(e) => Err(e),
+    };
+    match planned {
+        Ok(Some((round_id, checks))) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(json!({"roundId": round_id, "checks": checks}), None),
+        ),
+        Ok(None) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(json!({"roundId": null, "checks": []}), None),
+        ),
+        Err(e) => {
+            println!("Error planning hub round: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to plan round"),
+            )
+        }
+    }
+}
+
+/// Sets how often a website is checked. The new interval applies from the
+/// next check on.
+pub(crate) async fn set_check_interval_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Json(payload): Json<CheckIntervalUpdate>,
+) -> ApiJsonResponse {
+    if payload.check_interval_secs < MIN_CHECK_INTERVAL_SECS {
+        return ApiJsonResponse(
+            StatusCode::BAD_REQUEST,
+            json_error("Check interval must be at least 60 seconds"),
+        );
+    }
+
+    let result = sqlx::query(
+        "UPDATE websites SET check_interval_secs = $3
+         WHERE id = $1 AND user_id = $2 AND disabled = false",
+    )
+    .bind(website_id)
+    .bind(payload.user_id)
+    .bind(payload.check_interval_secs)
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({"id": website_id, "checkIntervalSecs": payload.check_interval_secs}),
+                Some("Check interval updated".to_string()),
+            ),
+        ),
+        Ok(_) => ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Website not found")),
+        Err(e) => {
+            println!("Error updating check interval: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to update check interval"),
+            )
+        }
+    }
+}
+
+/// Who checks what: connected validators with their load, and every HTTP
+/// website with its next check and the validators of its latest round.
+pub(crate) async fn get_schedule_handler(
+    Extension(pool): Extension<PgPool>,
+    Extension(gateway): Extension<ValidatorGateway>,
+) -> ApiJsonResponse {
+    let config = get_config();
+    let validators = match gateway_candidates(&pool, &gateway).await {
+        Ok(candidates) => candidates
+            .into_iter()
+            .map(|candidate| ScheduledValidator {
+                validator_id: candidate.validator_id,
+                location: candidate.location,
+                reputation: candidate.reputation,
+                load: candidate.load,
+            })
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            println!("Error fetching connected validators: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch schedule"),
+            );
+        }
+    };
+
+    let websites = sqlx::query_as::<_, ScheduledWebsite>(
+        "SELECT w.id, w.url, w.check_interval_secs, w.next_check_at,
+                last.round_id AS last_round_id, last.assigned_at AS last_assigned_at,
+                COALESCE(last.validator_ids, '{}') AS last_validator_ids
+         FROM websites w
+         LEFT JOIN LATERAL (
+             SELECT ca.round_id, MAX(ca.assigned_at) AS assigned_at,
+                    ARRAY_AGG(ca.validator_id ORDER BY ca.validator_id) AS validator_ids
+             FROM check_assignments ca
+             WHERE ca.website_id = w.id
+             GROUP BY ca.round_id
+             ORDER BY MAX(ca.assigned_at) DESC
+             LIMIT 1
+         ) last ON true
+         WHERE w.disabled = false AND w.monitor_type = 'http'
+         ORDER BY w.next_check_at, w.id",
+    )
+    .fetch_all(&pool)
+    .await;
+
+    match websites {
+        Ok(websites) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({
+                    "validatorsPerCheck": config.validators_per_check,
+                    "validators": validators,
+                    "websites": websites
+                }),
+                None,
+            ),
+        ),
+        Err(e) => {
+            println!("Error fetching schedule: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch schedule"),
+            )
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{Tick, TickCursor, TickQuery, TICK_COLUMNS},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+const DEFAULT_TICKS_PAGE_SIZE: i64 = 100;
+const MAX_TICKS_PAGE_SIZE: i64 = 1000;
+
+/// Tick history of one website, newest first. Pages are keyed on
+/// `(created_at, id)` so the scan stays on
+/// `idx_website_ticks_website_id_created_at`.
+pub(crate) async fn get_ticks_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Query(query): Query<TickQuery>,
+) -> ApiJsonResponse {
+    if let Some(status) = query.status.as_deref() {
+        if !matches!(status, "Good" | "Bad") {
+            return ApiJsonResponse(
+                StatusCode::BAD_REQUEST,
+                json_error("status must be one of Good, Bad"),
+            );
+        }
+    }
+    let cursor = match query.cursor.as_deref().map(TickCursor::decode) {
+        None => None,
+        Some(Some(cursor)) => Some(cursor),
+        Some(None) => return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("Invalid cursor")),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_TICKS_PAGE_SIZE).clamp(1, MAX_TICKS_PAGE_SIZE);
+
+    let website = sqlx::query("SELECT id FROM websites WHERE id = $1 AND user_id = $2 AND disabled = false")
+        .bind(website_id)
+        .bind(query.user_id)
+        .fetch_optional(&pool)
+        .await;
+
+    match website {
+        Ok(Some(_)) => {}
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Website not found")),
+        Err(e) => {
+            println!("Error fetching website: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch ticks"),
+            );
+        }
+    }
+
+    let sql = format!(
+        "SELECT {TICK_COLUMNS}
+         FROM website_ticks
+         WHERE website_id = $1
+           AND ($2::TIMESTAMP IS NULL OR created_at >= $2)
+           AND ($3::TIMESTAMP IS NULL OR created_at < $3)
+           AND ($4::UUID IS NULL OR validator_id = $4)
+           AND ($5::TEXT IS NULL OR status = $5)
+           AND ($6::TIMESTAMP IS NULL OR (created_at, id) < ($6, $7))
+           AND ($9::TEXT IS NULL OR validator_id IN (SELECT id FROM validators WHERE location = $9))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $8"
+    );
+    let ticks_result = sqlx::query_as::<_, Tick>(&sql)
+        .bind(website_id)
+        .bind(query.from.map(|from| from.naive_utc()))
+        .bind(query.to.map(|to| to.naive_utc()))
+        .bind(query.validator_id)
+        .bind(query.status.as_deref())
+        .bind(cursor.as_ref().map(|cursor| cursor.created_at))
+        .bind(cursor.as_ref().map(|cursor| cursor.id))
+        .bind(limit + 1)
+        .bind(query.region.as_deref())
+        .fetch_all(&pool)
+        .await;
+
+    match ticks_result {
+        Ok(mut ticks) => {
+            let has_more = ticks.len() as i64 > limit;
+            ticks.truncate(limit as usize);
+            let next_cursor = ticks.last().filter(|_| has_more).map(|tick| {
+                TickCursor { created_at: tick.created_at, id: tick.id }.encode()
+            });
+
+            ApiJsonResponse(
+                StatusCode::OK,
+                json_success(json!({"ticks": ticks, "nextCursor": next_cursor}), None),
+            )
+        }
+        Err(e) => {
+            println!("Error fetching ticks: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch ticks"),
+            )
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{UptimeQuery, UptimeStats},
+    rollups::{ceil_to_hour, rollup_watermark, truncate_to_hour},
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+/// Standard reporting windows, as (label, length in hours).
+pub(crate) const STANDARD_WINDOWS: [(&str, i64); 4] = [("24h", 24), ("7d", 24 * 7), ("30d", 24 * 30), ("90d", 24 * 90)];
+
+/// Uptime of every website in `website_ids` over `[from, to)`, in one query.
+///
+/// Whole hours that are already rolled up are read from
+/// `tick_rollups_hourly`; the partial hours at either end, hours not rolled up
+/// yet and hours touching a maintenance window are read from raw ticks.
+///
+/// Ticks inside a maintenance window are ignored and the maintenance time is
+/// taken out of the expected coverage. Overlapping maintenance windows are
+/// merged first so they are not counted twice.
+pub(crate) async fn uptime_for_websites(
+    pool: &PgPool,
+    website_ids: &[Uuid],
+    window: &str,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<HashMap<Uuid, UptimeStats>, sqlx::Error> {
+    let rollup_start = ceil_to_hour(from);
+    let rollup_end = match rollup_watermark(pool).await? {
+        Some(watermark) => truncate_to_hour(to).min(watermark).max(rollup_start),
+        None => rollup_start,
+    };
+
+    let rows = sqlx::query(
+        "WITH raw AS (
+             SELECT wt.website_id,
+                    COUNT(*) FILTER (WHERE wt.status = 'Good') AS up_ticks,
+                    COUNT(*) FILTER (WHERE wt.status <> 'Good') AS down_ticks,
+                    COUNT(DISTINCT date_trunc('minute', wt.created_at)) AS data_minutes
+             FROM website_ticks wt
+             WHERE wt.website_id = ANY($1)
+               AND wt.created_at >= $2 AND wt.created_at < $3
+               AND (wt.created_at < $4 OR wt.created_at >= $5 OR EXISTS (
+                   SELECT 1 FROM maintenance_windows mw
+                   WHERE mw.website_id = wt.website_id
+                     AND mw.starts_at < date_trunc('hour', wt.created_at) + INTERVAL '1 hour'
+                     AND mw.ends_at > date_trunc('hour', wt.created_at)
+               ))
+               AND NOT EXISTS (
+                   SELECT 1 FROM maintenance_windows mw
+                   WHERE mw.website_id = wt.website_id
+                     AND wt.created_at >= mw.starts_at AND wt.created_at < mw.ends_at
+               )
+             GROUP BY wt.website_id
+         ),
+         rolled AS (
+             SELECT website_id, SUM(up_ticks) AS up_ticks, SUM(down_ticks) AS down_ticks, SUM(data_minutes) AS data_minutes
+             FROM (
+                 SELECT r.website_id, r.bucket_start,
+                        SUM(r.up_count) AS up_ticks,
+                        SUM(r.down_count) AS down_ticks,
+                        MAX(r.active_minutes) AS data_minutes
+                 FROM tick_rollups_hourly r
+                 WHERE r.website_id = ANY($1)
+                   AND r.bucket_start >= $4 AND r.bucket_start < $5
+                   AND NOT EXISTS (
+                       SELECT 1 FROM maintenance_windows mw
+                       WHERE mw.website_id = r.website_id
+                         AND mw.starts_at < r.bucket_start + INTERVAL '1 hour'
+                         AND mw.ends_at > r.bucket_start
+                   )
+                 GROUP BY r.website_id, r.bucket_start
+             ) hours
+             GROUP BY website_id
+         ),
+         t AS (
+             SELECT website_id,
+                    SUM(up_ticks)::BIGINT AS up_ticks,
+                    SUM(down_ticks)::BIGINT AS down_ticks,
+                    SUM(data_minutes)::BIGINT AS data_minutes
+             FROM (SELECT * FROM raw UNION ALL SELECT * FROM rolled) counts
+             GROUP BY website_id
+         )
+         SELECT ids.website_id,
+                COALESCE(t.up_ticks, 0) AS up_ticks,
+                COALESCE(t.down_ticks, 0) AS down_ticks,
+                COALESCE(t.data_minutes, 0) AS data_minutes,
+                COALESCE(m.maintenance_seconds, 0) AS maintenance_seconds,
+                100.0 * t.up_ticks / NULLIF(t.up_ticks + t.down_ticks, 0)::FLOAT8 AS uptime_percentage
+         FROM UNNEST($1::UUID[]) AS ids(website_id)
+         LEFT JOIN t USING (website_id)
+         LEFT JOIN (
+             SELECT website_id, SUM(EXTRACT(EPOCH FROM upper(period) - lower(period)))::FLOAT8 AS maintenance_seconds
+             FROM (
+                 SELECT website_id, UNNEST(range_agg(tsrange(GREATEST(starts_at, $2), LEAST(ends_at, $3)))) AS period
+                 FROM maintenance_windows
+                 WHERE website_id = ANY($1) AND starts_at < $3 AND ends_at > $2
+                 GROUP BY website_id
+             ) merged
+             GROUP BY website_id
+         ) m USING (website_id)",
+    )
+    .bind(website_ids)
+    .bind(from)
+    .bind(to)
+    .bind(rollup_start)
+    .bind(rollup_end)
+    .fetch_all(pool)
+    .await?;
+
+    let window_seconds = (to - from).num_seconds() as f64;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let maintenance_seconds = row.get::<f64, _>("maintenance_seconds");
+            let expected_minutes = ((window_seconds - maintenance_seconds) / 60.0).floor();
+            let data_minutes = row.get::<i64, _>("data_minutes") as f64;
+            let stats = UptimeStats {
+                window: window.to_string(),
+                from,
+                to,
+                uptime_percentage: row.get::<Option<f64>, _>("uptime_percentage"),
+                up_ticks: row.get::<i64, _>("up_ticks"),
+                down_ticks: row.get::<i64, _>("down_ticks"),
+                maintenance_seconds,
+                coverage: (expected_minutes > 0.0).then(|| (data_minutes / expected_minutes).min(1.0)),
+            };
+            (row.get::<Uuid, _>("website_id"), stats)
+        })
+        .collect())
+}
+
+/// Uptime over every standard window, keyed by website then window label.
+pub(crate) async fn standard_uptime_for_websites(
+    pool: &PgPool,
+    website_ids: &[Uuid],
+) -> Result<HashMap<Uuid, HashMap<String, UptimeStats>>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let mut summary: HashMap<Uuid, HashMap<String, UptimeStats>> = HashMap::new();
+    for (label, hours) in STANDARD_WINDOWS {
+        let stats = uptime_for_websites(pool, website_ids, label, now - chrono::Duration::hours(hours), now).await?;
+        for (website_id, stats) in stats {
+            summary.entry(website_id).or_default().insert(label.to_string(), stats);
+        }
+    }
+    Ok(summary)
+}
+
+/// Uptime of one website. Without `from`/`to` every standard window is
+/// returned; otherwise a single custom window (`to` defaults to now).
+pub(crate) async fn get_uptime_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(website_id): Path<Uuid>,
+    Query(query): Query<UptimeQuery>,
+) -> ApiJsonResponse {
+    let website = sqlx::query("SELECT id FROM websites WHERE id = $1 AND user_id = $2 AND disabled = false")
+        .bind(website_id)
+        .bind(query.user_id)
+        .fetch_optional(&pool)
+        .await;
+
+    match website {
+        Ok(Some(_)) => {}
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Website not found")),
+        Err(e) => {
+            println!("Error fetching website: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to compute uptime"),
+            );
+        }
+    }
+
+    let windows = match (query.from, query.to) {
+        (None, None) => standard_uptime_for_websites(&pool, &[website_id])
+            .await
+            .map(|mut summary| {
+                let mut windows = summary.remove(&website_id).unwrap_or_default();
+                STANDARD_WINDOWS
+                    .iter()
+                    .filter_map(|(label, _)| windows.remove(*label))
+                    .collect::<Vec<_>>()
+            }),
+        (Some(from), to) => {
+            let to = to.unwrap_or_else(Utc::now);
+            if from >= to {
+                return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("from must be before to"));
+            }
+            uptime_for_websites(&pool, &[website_id], "custom", from.naive_utc(), to.naive_utc())
+                .await
+                .map(|stats| stats.into_values().collect::<Vec<_>>())
+        }
+        (None, Some(_)) => {
+            return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("from is required with to"));
+        }
+    };
+
+    match windows {
+        Ok(windows) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(json!({"websiteId": website_id, "windows": windows}), None),
+        ),
+        Err(e) => {
+            println!("Error computing uptime: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to compute uptime"),
+            )
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{Duration, Utc};
+use config::get_config;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::{
+    gateway::ValidatorGateway,
+    liveness::UPTIME_SQL,
+    models::{
+        ReputationEntry, ReputationHistoryQuery, ValidatorDetail, ValidatorStats, ValidatorStatus,
+        ValidatorStatusUpdate, ValidatorSummary,
+    },
+    routes::{json_error, json_success, ApiJsonResponse},
+};
+
+const STATS_WINDOW_HOURS: i64 = 24;
+
+/// One week of hourly scoring runs.
+const DEFAULT_HISTORY_LIMIT: i64 = 168;
+const MAX_HISTORY_LIMIT: i64 = 2000;
+
+/// Columns of `ValidatorSummary` for validators `v`; `$1` is now.
+fn summary_columns() -> String {
+    format!(
+        "v.id, v.public_key, v.location, v.country, v.region, v.city, v.latitude, v.longitude, v.asn,
+         v.as_organization, v.reputation, v.status, v.online, v.last_seen_at, {} AS uptime",
+        UPTIME_SQL
+    )
+}
+
+/// Validators with their current reputation and liveness, best first.
+pub(crate) async fn get_validators_handler(Extension(pool): Extension<PgPool>) -> ApiJsonResponse {
+    let result = sqlx::query_as::<_, ValidatorSummary>(&format!(
+        "SELECT {}
+         FROM validators v
+         ORDER BY v.reputation DESC, v.id",
+        summary_columns()
+    ))
+    .bind(Utc::now().naive_utc())
+    .fetch_all(&pool)
+    .await;
+
+    match result {
+        Ok(validators) => {
+            let online = validators.iter().filter(|validator| validator.online).count();
+            ApiJsonResponse(
+                StatusCode::OK,
+                json_success(
+                    json!({"total": validators.len(), "online": online, "validators": validators}),
+                    None,
+                ),
+            )
+        }
+        Err(e) => {
+            println!("Error fetching validators: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch validators"),
+            )
+        }
+    }
+}
+
+/// One validator with its pending payouts and what it did over the last
+/// 24 hours.
+pub(crate) async fn get_validator_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+) -> ApiJsonResponse {
+    let config = get_config();
+    let now = Utc::now().naive_utc();
+
+    let validator = match sqlx::query_as::<_, ValidatorDetail>(&format!(
+        "SELECT {}, v.pending_payouts, v.status_reason, v.status_changed_at
+         FROM validators v
+         WHERE v.id = $2",
+        summary_columns()
+    ))
+    .bind(now)
+    .bind(validator_id)
+    .fetch_optional(&pool)
+    .await
+    {
+        Ok(Some(validator)) => validator,
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Validator not found")),
+        Err(e) => {
+            println!("Error fetching validator: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch validator"),
+            );
+        }
+    };
+
+    // Checks still waiting for an answer don't count against the response rate
+    let answerable_until = now - Duration::seconds(config.validator_response_timeout_secs as i64);
+    let stats = sqlx::query_as::<_, ValidatorStats>(
+        "SELECT COUNT(*) AS checks_performed,
+                AVG(t.latency) AS average_latency,
+                (SELECT COUNT(answer.id)::FLOAT8 / NULLIF(COUNT(*), 0)
+                 FROM check_assignments ca
+                 LEFT JOIN website_ticks answer
+                   ON answer.validator_id = ca.validator_id
+                  AND answer.website_id = ca.website_id
+                  AND answer.round_id = ca.round_id
+                 WHERE ca.validator_id = $1 AND ca.assigned_at >= $2 AND ca.assigned_at < $3) AS response_rate
+         FROM website_ticks t
+         WHERE t.validator_id = $1 AND t.created_at >= $2",
+    )
+    .bind(validator_id)
+    .bind(now - Duration::hours(STATS_WINDOW_HOURS))
+    .bind(answerable_until)
+    .fetch_one(&pool)
+    .await;
+
+    match stats {
+        Ok(stats) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(json!({"validator": validator, "stats": stats}), None),
+        ),
+        Err(e) => {
+            println!("Error fetching validator stats: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch validator"),
+            )
+        }
+    }
+}
+
+/// Reputation history of a validator, newest first.
+pub(crate) async fn get_reputation_history_handler(
+    Extension(pool): Extension<PgPool>,
+    Path(validator_id): Path<Uuid>,
+    Query(query): Query<ReputationHistoryQuery>,
+) -> ApiJsonResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+
+    let reputation = match sqlx::query_scalar::<_, f64>("SELECT reputation FROM validators WHERE id = $1")
+        .bind(validator_id)
+        .fetch_optional(&pool)
+        .await
+    {
+        Ok(Some(reputation)) => reputation,
+        Ok(None) => return ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Validator not found")),
+        Err(e) => {
+            println!("Error fetching validator: {:?}", e);
+            return ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch reputation history"),
+            );
+        }
+    };
+
+    let result = sqlx::query_as::<_, ReputationEntry>(
+        "SELECT computed_at, score, agreement, responsiveness, latency_plausibility,
+                expected_rounds, answered_rounds, agreed, disagreed
+         FROM validator_reputation_history
+         WHERE validator_id = $1
+         ORDER BY computed_at DESC
+         LIMIT $2",
+    )
+    .bind(validator_id)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await;
+
+    match result {
+        Ok(history) => ApiJsonResponse(
+            StatusCode::OK,
+            json_success(
+                json!({"validatorId": validator_id, "reputation": reputation, "history": history}),
+                None,
+            ),
+        ),
+        Err(e) => {
+            println!("Error fetching reputation history: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to fetch reputation history"),
+            )
+        }
+    }
+}
+
+/// What became of a status change.
+enum StatusChange {
+    Changed,
+    NotFound,
+    /// The validator's current status doesn't allow it.
+    Refused(ValidatorStatus),
+}
+
+async fn update_status(
+    pool: &PgPool,
+    validator_id: Uuid,
+    next: ValidatorStatus,
+    reason: Option<&str>,
+) -> Result<StatusChange, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let current = match sqlx::query("SELECT status FROM validators WHERE id = $1 FOR UPDATE")
+        .bind(validator_id)
+        .fetch_optional(&mut *tx)
+        .await?
+    {
+        Some(row) => row.get::<ValidatorStatus, _>("status"),
+        None => return Ok(StatusChange::NotFound),
+    };
+    if !current.can_become(next) {
+        return Ok(StatusChange::Refused(current));
+    }
+
+    sqlx::query("UPDATE validators SET status = $2, status_reason = $3, status_changed_at = $4 WHERE id = $1")
+        .bind(validator_id)
+        .bind(next)
+        .bind(reason)
+        .bind(Utc::now().naive_utc())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(StatusChange::Changed)
+}
+
+/// Moves a validator to `next`. Suspending and banning need a reason; a
+/// banned validator is also disconnected.
+async fn change_status(
+    pool: &PgPool,
+    gateway: &ValidatorGateway,
+    validator_id: Uuid,
+    next: ValidatorStatus,
+    payload: ValidatorStatusUpdate,
+) -> ApiJsonResponse {
+    let reason = payload.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    if next != ValidatorStatus::Active && reason.is_none() {
+        return ApiJsonResponse(StatusCode::BAD_REQUEST, json_error("A reason is required"));
+    }
+
+    match update_status(pool, validator_id, next, reason).await {
+        Ok(StatusChange::Changed) => {
+            if next == ValidatorStatus::Banned {
+                gateway.disconnect(validator_id, "Validator is banned".to_string());
+            }
+            ApiJsonResponse(
+                StatusCode::OK,
+                json_success(
+                    json!({"id": validator_id, "status": next, "statusReason": reason}),
+                    Some("Validator status updated".to_string()),
+                ),
+            )
+        }
+        Ok(StatusChange::NotFound) => ApiJsonResponse(StatusCode::NOT_FOUND, json_error("Validator not found")),
+        Ok(StatusChange::Refused(current)) => ApiJsonResponse(
+            StatusCode::CONFLICT,
+            json_error(&format!("Validator is {} and can't become {}", current.as_str(), next.as_str())),
+        ),
+        Err(e) => {
+            println!("Error updating validator status: {:?}", e);
+            ApiJsonResponse(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json_error("Failed to update validator status"),
+            )
+        }
+    }
+}
+
+/// Activates a pending validator or reinstates a suspended one.
+pub(crate) async fn approve_validator_handler(
+    Extension(pool): Extension<PgPool>,
+    Extension(gateway): Extension<ValidatorGateway>,
+    Path(validator_id): Path<Uuid>,
+    payload: Option<Json<ValidatorStatusUpdate>>,
+) -> ApiJsonResponse {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    change_status(&pool, &gateway, validator_id, ValidatorStatus::Active, payload).await
+}
+
+pub(crate) async fn suspend_validator_handler(
+    Extension(pool): Extension<PgPool>,
+    Extension(gateway): Extension<ValidatorGateway>,
+    Path(validator_id): Path<Uuid>,
+    Json(payload): Json<ValidatorStatusUpdate>,
+) -> ApiJsonResponse {
+    change_status(&pool, &gateway, validator_id, ValidatorStatus::Suspended, payload).await
+}
+
+pub(crate) async fn ban_validator_handler(
+    Extension(pool): Extension<PgPool>,
+    Extension(gateway): Extension<ValidatorGateway>,
+    Path(validator_id): Path<Uuid>,
+    Json(payload): Json<ValidatorStatusUpdate>,
+) -> ApiJsonResponse {
+    change_status(&pool, &gateway, validator_id, ValidatorStatus::Banned, payload).await
+}
@@ -0,0 +1,440 @@
+use std::{collections::HashSet, future::Future, str::FromStr};
+
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDateTime, Utc};
+use config::Config;
+use protocol::tick::TickReport;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+const MIN_NONCE_LEN: usize = 16;
+const MAX_NONCE_LEN: usize = 128;
+
+/// How old, or how far ahead of our clock, a report may be.
+#[derive(Debug, Clone, Copy)]
+pub struct FreshnessWindow {
+    pub max_age: ChronoDuration,
+    pub max_skew: ChronoDuration,
+}
+
+impl FreshnessWindow {
+    pub fn from_config(config: &Config) -> Self {
+        FreshnessWindow {
+            max_age: ChronoDuration::seconds(config.tick_max_age_secs),
+            max_skew: ChronoDuration::seconds(config.tick_max_clock_skew_secs),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum VerificationError {
+    MalformedNonce,
+    Stale,
+    FromTheFuture,
+    BadSignature,
+    Replayed,
+    Database(sqlx::Error),
+}
+
+/// Remembers the nonces validators have used.
+pub trait NonceStore {
+    /// Records a nonce until `expires_at`; `false` when the validator has
+    /// used it before.
+    fn claim(
+        &mut self,
+        validator_id: Uuid,
+        nonce: &str,
+        expires_at: NaiveDateTime,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+}
+
+/// Nonces in `validator_nonces`, claimed on the connection of the
+/// transaction that records the tick, so a failed write releases them.
+pub struct PgNonceStore<'c>(pub &'c mut PgConnection);
+
+impl NonceStore for PgNonceStore<'_> {
+    async fn claim(&mut self, validator_id: Uuid, nonce: &str, expires_at: NaiveDateTime) -> Result<bool, sqlx::Error> {
+        let claimed = sqlx::query(
+            "INSERT INTO validator_nonces (validator_id, nonce, expires_at) VALUES ($1, $2, $3)
+             ON CONFLICT (validator_id, nonce) DO NOTHING",
+        )
+        .bind(validator_id)
+        .bind(nonce)
+        .bind(expires_at)
+        .execute(&mut *self.0)
+        .await?
+        .rows_affected();
+        Ok(claimed == 1)
+    }
+}
+
+/// In-memory store; nonces never expire.
+impl NonceStore for HashSet<(Uuid, String)> {
+    async fn claim(&mut self, validator_id: Uuid, nonce: &str, _expires_at: NaiveDateTime) -> Result<bool, sqlx::Error> {
+        Ok(self.insert((validator_id, nonce.to_string())))
+    }
+}
+
+/// Prefix of what an operator signs to call its validator's routes.
+pub const OPERATOR_REQUEST_PREFIX: &str = "dews-operator-v1";
+
+/// When something signed at `timestamp` (unix milliseconds) was signed, if
+/// that is within `window` of `now`.
+fn check_fresh(timestamp: i64, now: DateTime<Utc>, window: FreshnessWindow) -> Result<DateTime<Utc>, VerificationError> {
+    let signed_at = DateTime::<Utc>::from_timestamp_millis(timestamp).ok_or(VerificationError::Stale)?;
+    if signed_at < now - window.max_age {
+        return Err(VerificationError::Stale);
+    }
+    if signed_at > now + window.max_skew {
+        return Err(VerificationError::FromTheFuture);
+    }
+    Ok(signed_at)
+}
+
+/// Checks a base58 signature of `payload` by `public_key`.
+fn check_signature(public_key: &str, signature: &str, payload: &[u8]) -> Result<(), VerificationError> {
+    let pubkey = Pubkey::from_str(public_key).map_err(|_| VerificationError::BadSignature)?;
+    let signature = Signature::from_str(signature).map_err(|_| VerificationError::BadSignature)?;
+    if !signature.verify(pubkey.as_ref(), payload) {
+        return Err(VerificationError::BadSignature);
+    }
+    Ok(())
+}
+
+/// What an operator signs to send `method` to `path_and_query`, e.g.
+/// `GET /api/validators/<id>/payouts?format=csv`, at `timestamp`. Requests
+/// other than `GET` also sign their `nonce`.
+pub fn operator_request_payload(method: &str, path_and_query: &str, timestamp: i64, nonce: Option<&str>) -> Vec<u8> {
+    let mut payload = format!("{}\n{}\n{}\n{}", OPERATOR_REQUEST_PREFIX, method, path_and_query, timestamp);
+    if let Some(nonce) = nonce {
+        payload.push('\n');
+        payload.push_str(nonce);
+    }
+    payload.into_bytes()
+}
+
+/// A signed request to one of a validator's operator routes.
+#[derive(Debug, Clone, Copy)]
+pub struct OperatorRequest<'a> {
+    pub validator_id: Uuid,
+    pub method: &'a str,
+    pub path_and_query: &'a str,
+    pub timestamp: i64,
+    pub nonce: Option<&'a str>,
+    pub signature: &'a str,
+}
+
+/// Checks that an operator request is fresh and signed by the validator's
+/// `public_key`. A replayed `GET` within the window only reads again, but
+/// other methods act, e.g. request a payout, so they need a nonce, which is
+/// claimed like a report's.
+pub async fn verify_operator_request<S: NonceStore>(
+    request: OperatorRequest<'_>,
+    public_key: &str,
+    now: DateTime<Utc>,
+    window: FreshnessWindow,
+    nonces: &mut S,
+) -> Result<(), VerificationError> {
+    let nonce = match request.method {
+        "GET" => None,
+        _ => {
+            let nonce = request.nonce.ok_or(VerificationError::MalformedNonce)?;
+            check_nonce(nonce)?;
+            Some(nonce)
+        }
+    };
+
+    let signed_at = check_fresh(request.timestamp, now, window)?;
+    let payload = operator_request_payload(request.method, request.path_and_query, request.timestamp, nonce);
+    check_signature(public_key, request.signature, &payload)?;
+
+    let Some(nonce) = nonce else {
+        return Ok(());
+    };
+    claim_nonce(nonces, request.validator_id, nonce, signed_at, window).await
+}
+
+/// Checks that a nonce is 16 to 128 characters of `[A-Za-z0-9_-]`.
+fn check_nonce(nonce: &str) -> Result<(), VerificationError> {
+    let nonce_ok = (MIN_NONCE_LEN..=MAX_NONCE_LEN).contains(&nonce.len())
+        && nonce
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+    if !nonce_ok {
+        return Err(VerificationError::MalformedNonce);
+    }
+    Ok(())
+}
+
+/// Claims a nonce until what it was signed into would be stale anyway.
+async fn claim_nonce<S: NonceStore>(
+    nonces: &mut S,
+    validator_id: Uuid,
+    nonce: &str,
+    signed_at: DateTime<Utc>,
+    window: FreshnessWindow,
+) -> Result<(), VerificationError> {
+    let expires_at = (signed_at + window.max_age).naive_utc();
+    match nonces.claim(validator_id, nonce, expires_at).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(VerificationError::Replayed),
+        Err(e) => Err(VerificationError::Database(e)),
+    }
+}
+
+/// Checks that a report is fresh, signed by `public_key` and not a replay,
+/// in that order, so that forged or stale reports never claim a nonce.
+/// Nonces are kept until the report would be stale anyway.
+pub async fn verify_report<S: NonceStore>(
+    report: &TickReport,
+    public_key: &str,
+    now: DateTime<Utc>,
+    window: FreshnessWindow,
+    nonces: &mut S,
+) -> Result<(), VerificationError> {
+    check_nonce(&report.nonce)?;
+    let signed_at = check_fresh(report.timestamp, now, window)?;
+    check_signature(public_key, &report.signature, &report.signed_payload())?;
+    claim_nonce(nonces, report.validator_id, &report.nonce, signed_at, window).await
+}
+
+/// Forgets nonces whose reports would be rejected as stale by now.
+pub async fn prune_expired_nonces(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    Ok(sqlx::query("DELETE FROM validator_nonces WHERE expires_at < $1")
+        .bind(Utc::now().naive_utc())
+        .execute(pool)
+        .await?
+        .rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::{Keypair, Signer};
+
+    use super::*;
+    use protocol::tick::{ErrorClass, TickReportTimings};
+
+    fn window() -> FreshnessWindow {
+        FreshnessWindow {
+            max_age: ChronoDuration::seconds(120),
+            max_skew: ChronoDuration::seconds(30),
+        }
+    }
+
+    fn signed_report(keypair: &Keypair, now: DateTime<Utc>) -> TickReport {
+        let mut report = TickReport {
+            website_id: Uuid::new_v4(),
+            validator_id: Uuid::new_v4(),
+            round_id: Uuid::new_v4(),
+            status: "Bad".to_string(),
+            latency: 182.5,
+            timestamp: now.timestamp_millis(),
+            nonce: "c0ffee00c0ffee00c0ffee00".to_string(),
+            http_status_code: Some(503),
+            error_class: Some(ErrorClass::AssertionFailed),
+            response_size_bytes: Some(1024),
+            timings: TickReportTimings {
+                dns_ms: Some(3.0),
+                connect_ms: None,
+                tls_ms: None,
+                ttfb_ms: Some(150.25),
+            },
+            signature: String::new(),
+        };
+        report.signature = keypair.sign_message(&report.signed_payload()).to_string();
+        report
+    }
+
+    async fn verify(report: &TickReport, keypair: &Keypair, now: DateTime<Utc>, nonces: &mut HashSet<(Uuid, String)>) -> Result<(), VerificationError> {
+        verify_report(report, &keypair.pubkey().to_string(), now, window(), nonces).await
+    }
+
+    #[tokio::test]
+    async fn accepts_signed_fresh_report() {
+        let keypair = Keypair::new();
+        let now = Utc::now();
+        let report = signed_report(&keypair, now);
+        assert!(verify(&report, &keypair, now, &mut HashSet::new()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn payload_matches_javascript_number_formatting() {
+        let keypair = Keypair::new();
+        let mut report = signed_report(&keypair, Utc::now());
+        report.latency = 120.0;
+        let payload = String::from_utf8(report.signed_payload()).unwrap();
+        let lines = payload.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "dews-tick-v2");
+        assert_eq!(lines[5], "120");
+        assert_eq!(lines[9], "assertion_failed");
+        assert_eq!(&lines[11..], ["3", "", "", "150.25"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_forged_result() {
+        let keypair = Keypair::new();
+        let now = Utc::now();
+        let mut nonces = HashSet::new();
+
+        let mut report = signed_report(&keypair, now);
+        report.status = "Good".to_string();
+        assert!(matches!(verify(&report, &keypair, now, &mut nonces).await, Err(VerificationError::BadSignature)));
+
+        let mut report = signed_report(&keypair, now);
+        report.latency = 20.0;
+        assert!(matches!(verify(&report, &keypair, now, &mut nonces).await, Err(VerificationError::BadSignature)));
+
+        let mut report = signed_report(&keypair, now);
+        report.website_id = Uuid::new_v4();
+        assert!(matches!(verify(&report, &keypair, now, &mut nonces).await, Err(VerificationError::BadSignature)));
+
+        let mut report = signed_report(&keypair, now);
+        report.timings.ttfb_ms = None;
+        assert!(matches!(verify(&report, &keypair, now, &mut nonces).await, Err(VerificationError::BadSignature)));
+
+        // Forgeries must not use up the nonce of the genuine report
+        assert!(nonces.is_empty());
+        let report = signed_report(&keypair, now);
+        assert!(verify(&report, &keypair, now, &mut nonces).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_report_signed_by_another_key() {
+        let keypair = Keypair::new();
+        let impostor = Keypair::new();
+        let now = Utc::now();
+        let report = signed_report(&impostor, now);
+        assert!(matches!(
+            verify(&report, &keypair, now, &mut HashSet::new()).await,
+            Err(VerificationError::BadSignature)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_replayed_report() {
+        let keypair = Keypair::new();
+        let now = Utc::now();
+        let mut nonces = HashSet::new();
+        let report = signed_report(&keypair, now);
+
+        assert!(verify(&report, &keypair, now, &mut nonces).await.is_ok());
+        assert!(matches!(
+            verify(&report, &keypair, now + ChronoDuration::seconds(5), &mut nonces).await,
+            Err(VerificationError::Replayed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_stale_and_future_reports() {
+        let keypair = Keypair::new();
+        let now = Utc::now();
+        let mut nonces = HashSet::new();
+
+        let report = signed_report(&keypair, now - ChronoDuration::seconds(121));
+        assert!(matches!(verify(&report, &keypair, now, &mut nonces).await, Err(VerificationError::Stale)));
+
+        let report = signed_report(&keypair, now + ChronoDuration::seconds(31));
+        assert!(matches!(verify(&report, &keypair, now, &mut nonces).await, Err(VerificationError::FromTheFuture)));
+
+        // A replay after the window is stale, so expired nonces can be forgotten
+        let report = signed_report(&keypair, now);
+        assert!(verify(&report, &keypair, now, &mut nonces).await.is_ok());
+        nonces.clear();
+        assert!(matches!(
+            verify(&report, &keypair, now + ChronoDuration::seconds(121), &mut nonces).await,
+            Err(VerificationError::Stale)
+        ));
+    }
+
+    #[tokio::test]
+    async fn verifies_operator_requests() {
+        let keypair = Keypair::new();
+        let public_key = keypair.pubkey().to_string();
+        let now = Utc::now();
+        let mut nonces = HashSet::new();
+        let path = "/api/validators/00000000-0000-0000-0000-0000000000dd/payouts?format=csv";
+        let timestamp = now.timestamp_millis();
+        let signature = keypair.sign_message(&operator_request_payload("GET", path, timestamp, None)).to_string();
+        let request = OperatorRequest {
+            validator_id: Uuid::new_v4(),
+            method: "GET",
+            path_and_query: path,
+            timestamp,
+            nonce: None,
+            signature: &signature,
+        };
+
+        assert!(verify_operator_request(request, &public_key, now, window(), &mut nonces).await.is_ok());
+        // Reads may be repeated within the window
+        assert!(verify_operator_request(request, &public_key, now, window(), &mut nonces).await.is_ok());
+        // The signature covers the method, path and query
+        let unsigned_path = OperatorRequest {
+            path_and_query: "/api/validators/00000000-0000-0000-0000-0000000000dd/payouts",
+            ..request
+        };
+        assert!(matches!(
+            verify_operator_request(unsigned_path, &public_key, now, window(), &mut nonces).await,
+            Err(VerificationError::BadSignature)
+        ));
+        assert!(matches!(
+            verify_operator_request(request, &Keypair::new().pubkey().to_string(), now, window(), &mut nonces).await,
+            Err(VerificationError::BadSignature)
+        ));
+        assert!(matches!(
+            verify_operator_request(request, &public_key, now + ChronoDuration::seconds(121), window(), &mut nonces).await,
+            Err(VerificationError::Stale)
+        ));
+    }
+
+    #[tokio::test]
+    async fn operator_requests_other_than_get_need_a_fresh_nonce() {
+        let keypair = Keypair::new();
+        let public_key = keypair.pubkey().to_string();
+        let now = Utc::now();
+        let mut nonces = HashSet::new();
+        let path = "/api/validators/00000000-0000-0000-0000-0000000000dd/payouts";
+        let timestamp = now.timestamp_millis();
+        let nonce = "c0ffee00c0ffee00c0ffee00";
+        let signature = keypair.sign_message(&operator_request_payload("POST", path, timestamp, Some(nonce))).to_string();
+        let request = OperatorRequest {
+            validator_id: Uuid::new_v4(),
+            method: "POST",
+            path_and_query: path,
+            timestamp,
+            nonce: Some(nonce),
+            signature: &signature,
+        };
+
+        assert!(matches!(
+            verify_operator_request(OperatorRequest { nonce: None, ..request }, &public_key, now, window(), &mut nonces).await,
+            Err(VerificationError::MalformedNonce)
+        ));
+        // The nonce is signed
+        let other_nonce = OperatorRequest {
+            nonce: Some("deadbeefdeadbeefdeadbeef"),
+            ..request
+        };
+        assert!(matches!(
+            verify_operator_request(other_nonce, &public_key, now, window(), &mut nonces).await,
+            Err(VerificationError::BadSignature)
+        ));
+        assert!(verify_operator_request(request, &public_key, now, window(), &mut nonces).await.is_ok());
+        assert!(matches!(
+            verify_operator_request(request, &public_key, now, window(), &mut nonces).await,
+            Err(VerificationError::Replayed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_nonce() {
+        let keypair = Keypair::new();
+        let now = Utc::now();
+        let mut report = signed_report(&keypair, now);
+        report.nonce = "short".to_string();
+        report.signature = keypair.sign_message(&report.signed_payload()).to_string();
+        assert!(matches!(
+            verify(&report, &keypair, now, &mut HashSet::new()).await,
+            Err(VerificationError::MalformedNonce)
+        ));
+    }
+}
@@ -1,16 +1,110 @@
-use std::env;
+use std::{env, str::FromStr};
 
 pub struct Config {
     pub host_port: String,
+    pub public_url: String,
+    /// Shared secret for `/api/admin` routes, sent as `X-Admin-Token`. Admin
+    /// routes are disabled when unset.
+    pub admin_token: Option<String>,
+    pub tick_retention_days: i32,
+    pub rollup_retention_days: i32,
+    pub prune_batch_size: i64,
+    pub prune_interval_secs: u64,
+    /// Quorum rule for website status consensus: `majority` or `<n>-of-<m>`.
+    pub consensus_rule: String,
+    /// Distinct validator locations that must fail before a website is down.
+    pub consensus_min_locations: usize,
+    /// Weigh consensus votes by validator reputation.
+    pub consensus_reputation_weighting: bool,
+    /// Scale validator payouts by reputation.
+    pub payout_reputation_weighting: bool,
+    /// MaxMind-format city database used to resolve validator locations.
+    /// Resolution is off when unset.
+    pub geoip_database: Option<String>,
+    /// Separate ASN database; ASNs are read from `geoip_database` when unset.
+    pub geoip_asn_database: Option<String>,
+    /// Lamports credited to a validator's pending payouts per accepted tick.
+    pub cost_per_validation: i32,
+    /// Tick reports older than this are rejected as stale.
+    pub tick_max_age_secs: i64,
+    /// How far a report's timestamp may be ahead of the backend clock.
+    pub tick_max_clock_skew_secs: i64,
+    /// How often the scheduler looks for websites that are due.
+    pub scheduler_poll_secs: u64,
+    /// Validators each due website is sent to.
+    pub validators_per_check: usize,
+    /// How long the gateway waits for a validator to answer a check.
+    pub validator_response_timeout_secs: u64,
+    /// A validator without a heartbeat for this long is marked offline.
+    pub validator_offline_after_secs: i64,
+    /// Activate new validators on signup instead of waiting for an admin.
+    pub validator_auto_approve: bool,
+    /// How often payouts left in flight are checked against the chain.
+    pub payout_reconcile_interval_secs: u64,
+    /// Lamports a validator must be owed to be paid by a batch or a
+    /// scheduled payout run.
+    pub payout_batch_threshold: i32,
+    /// Pay validators on a schedule instead of only on request.
+    pub payout_scheduler_enabled: bool,
+    /// How often the payout scheduler runs.
+    pub payout_interval_secs: u64,
+    /// Lamports a scheduled payout run may transfer in all.
+    pub payout_run_spend_cap: i64,
     // pub clerk_jwt_public_key: String
 }
 
+impl Config {
+    /// Rejects settings the background jobs can't run with.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.prune_batch_size <= 0 {
+            return Err("PRUNE_BATCH_SIZE must be positive".to_string());
+        }
+        if self.validators_per_check == 0 {
+            return Err("VALIDATORS_PER_CHECK must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
 pub fn get_config() -> Config {
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port = env::var("PORT").unwrap_or_else(|_| "8000".to_string());
     // let key=env::var("CLERK_SECRET_KEY").unwrap();
+    let public_url = env::var("PUBLIC_URL").unwrap_or_else(|_| format!("http://{}:{}", host, port));
     Config {
         host_port: format!("{}:{}", host, port),
+        public_url,
+        admin_token: env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty()),
+        tick_retention_days: env_or("TICK_RETENTION_DAYS", 30),
+        rollup_retention_days: env_or("ROLLUP_RETENTION_DAYS", 365),
+        prune_batch_size: env_or("PRUNE_BATCH_SIZE", 5000),
+        prune_interval_secs: env_or("PRUNE_INTERVAL_SECS", 3600),
+        consensus_rule: env::var("CONSENSUS_RULE").unwrap_or_else(|_| "majority".to_string()),
+        consensus_min_locations: env_or("CONSENSUS_MIN_LOCATIONS", 0),
+        consensus_reputation_weighting: env_or("CONSENSUS_REPUTATION_WEIGHTING", false),
+        payout_reputation_weighting: env_or("PAYOUT_REPUTATION_WEIGHTING", false),
+        geoip_database: env::var("GEOIP_DATABASE").ok().filter(|path| !path.is_empty()),
+        geoip_asn_database: env::var("GEOIP_ASN_DATABASE").ok().filter(|path| !path.is_empty()),
+        cost_per_validation: env_or("COST_PER_VALIDATION", 100),
+        tick_max_age_secs: env_or("TICK_MAX_AGE_SECS", 120),
+        tick_max_clock_skew_secs: env_or("TICK_MAX_CLOCK_SKEW_SECS", 30),
+        scheduler_poll_secs: env_or("SCHEDULER_POLL_SECS", 5),
+        validators_per_check: env_or("VALIDATORS_PER_CHECK", 3),
+        validator_response_timeout_secs: env_or("VALIDATOR_RESPONSE_TIMEOUT_SECS", 30),
+        validator_offline_after_secs: env_or("VALIDATOR_OFFLINE_AFTER_SECS", 90),
+        validator_auto_approve: env_or("VALIDATOR_AUTO_APPROVE", false),
+        payout_reconcile_interval_secs: env_or("PAYOUT_RECONCILE_INTERVAL_SECS", 60),
+        payout_batch_threshold: env_or("PAYOUT_BATCH_THRESHOLD", 1_000_000),
+        payout_scheduler_enabled: env_or("PAYOUT_SCHEDULER_ENABLED", false),
+        payout_interval_secs: env_or("PAYOUT_INTERVAL_SECS", 86400),
+        payout_run_spend_cap: env_or("PAYOUT_RUN_SPEND_CAP", 10_000_000_000),
         // clerk_jwt_public_key:key
     }
 }
@@ -0,0 +1,322 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use uuid::Uuid;
+
+/// One validator's report for a website in a check round.
+#[derive(Debug, Clone)]
+pub struct Vote {
+    /// `None` for ticks recorded by the backend itself, e.g. missed heartbeats.
+    pub validator_id: Option<Uuid>,
+    /// Validator location; `None` when it is not known.
+    pub location: Option<String>,
+    pub up: bool,
+    pub latency: f64,
+    /// Failure reason reported with a down vote.
+    pub reason: Option<String>,
+    /// Voting weight used by the majority rule.
+    pub weight: f64,
+}
+
+/// How the votes of a round are turned into a status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuorumRule {
+    /// The side with the larger total weight wins; ties are inconclusive.
+    Majority,
+    /// At least `m` validators must report, and a status needs `n` of them.
+    /// Down wins when both sides reach `n`.
+    NOfM { n: usize, m: usize },
+}
+
+impl FromStr for QuorumRule {
+    type Err = String;
+
+    /// Parses `majority` or `<n>-of-<m>`, e.g. `2-of-3`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("majority") {
+            return Ok(QuorumRule::Majority);
+        }
+        let (n, m) = value
+            .split_once("-of-")
+            .ok_or_else(|| format!("unknown quorum rule '{}'", value))?;
+        let n = n.parse::<usize>().map_err(|_| format!("invalid quorum rule '{}'", value))?;
+        let m = m.parse::<usize>().map_err(|_| format!("invalid quorum rule '{}'", value))?;
+        if n == 0 || n > m {
+            return Err(format!("quorum rule '{}' needs 0 < n <= m", value));
+        }
+        Ok(QuorumRule::NOfM { n, m })
+    }
+}
+
+impl fmt::Display for QuorumRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuorumRule::Majority => write!(f, "majority"),
+            QuorumRule::NOfM { n, m } => write!(f, "{}-of-{}", n, m),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConsensusConfig {
+    pub rule: QuorumRule,
+    /// A down verdict also needs failures from this many distinct known
+    /// locations, otherwise the round is only degraded. 0 disables the check.
+    pub min_locations: usize,
+    /// Votes carry their validator's reputation as weight instead of 1.
+    pub reputation_weighting: bool,
+}
+
+impl ConsensusConfig {
+    /// Settings for heartbeat monitors, whose only vote in a round is the
+    /// backend's own tick. The quorum and location rules are meant for
+    /// validator checks and would leave every heartbeat round inconclusive
+    /// or degraded.
+    pub fn for_heartbeats(&self) -> ConsensusConfig {
+        ConsensusConfig {
+            rule: QuorumRule::Majority,
+            min_locations: 0,
+            reputation_weighting: false,
+        }
+    }
+}
+
+impl Default for ConsensusConfig {
+    fn default() -> Self {
+        ConsensusConfig {
+            rule: QuorumRule::Majority,
+            min_locations: 0,
+            reputation_weighting: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsensusStatus {
+    Up,
+    Down,
+    /// Enough validators failed, but not from enough locations.
+    Degraded,
+    /// Too few votes, or a tie.
+    Inconclusive,
+}
+
+impl ConsensusStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsensusStatus::Up => "up",
+            ConsensusStatus::Down => "down",
+            ConsensusStatus::Degraded => "degraded",
+            ConsensusStatus::Inconclusive => "inconclusive",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    pub status: ConsensusStatus,
+    pub up_votes: usize,
+    pub down_votes: usize,
+    /// Distinct known locations that reported in the round.
+    pub locations: usize,
+    /// Median latency of the up votes.
+    pub latency: Option<f64>,
+    pub failing_validators: Vec<Uuid>,
+    pub error_reasons: Vec<String>,
+}
+
+/// Derives the status of a website for one check round.
+pub fn decide(votes: &[Vote], config: &ConsensusConfig) -> Verdict {
+    let (up, down): (Vec<&Vote>, Vec<&Vote>) = votes.iter().partition(|vote| vote.up);
+
+    let mut status = match config.rule {
+        QuorumRule::Majority => {
+            let up_weight: f64 = up.iter().map(|vote| vote.weight).sum();
+            let down_weight: f64 = down.iter().map(|vote| vote.weight).sum();
+            if up_weight > down_weight {
+                ConsensusStatus::Up
+            } else if down_weight > up_weight {
+                ConsensusStatus::Down
+            } else {
+                ConsensusStatus::Inconclusive
+            }
+        }
+        QuorumRule::NOfM { n, m } => {
+            if votes.len() < m {
+                ConsensusStatus::Inconclusive
+            } else if down.len() >= n {
+                ConsensusStatus::Down
+            } else if up.len() >= n {
+                ConsensusStatus::Up
+            } else {
+                ConsensusStatus::Inconclusive
+            }
+        }
+    };
+
+    let failing_locations = distinct_locations(&down);
+    if status == ConsensusStatus::Down && failing_locations < config.min_locations {
+        status = ConsensusStatus::Degraded;
+    }
+
+    let mut latencies = up.iter().map(|vote| vote.latency).collect::<Vec<_>>();
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let latency = latencies.get(latencies.len() / 2).copied();
+
+    Verdict {
+        status,
+        up_votes: up.len(),
+        down_votes: down.len(),
+        locations: distinct_locations(&votes.iter().collect::<Vec<_>>()),
+        latency,
+        failing_validators: down
+            .iter()
+            .filter_map(|vote| vote.validator_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        error_reasons: down
+            .iter()
+            .filter_map(|vote| vote.reason.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn distinct_locations(votes: &[&Vote]) -> usize {
+    votes
+        .iter()
+        .filter_map(|vote| vote.location.as_deref())
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn vote(up: bool, location: Option<&str>) -> Vote {
+        Vote {
+            validator_id: Some(Uuid::from_u128(NEXT_ID.fetch_add(1, Ordering::Relaxed) as u128)),
+            location: location.map(str::to_string),
+            up,
+            latency: if up { 100.0 } else { 0.0 },
+            reason: (!up).then(|| "Timeout".to_string()),
+            weight: 1.0,
+        }
+    }
+
+    fn config(rule: QuorumRule) -> ConsensusConfig {
+        ConsensusConfig {
+            rule,
+            ..ConsensusConfig::default()
+        }
+    }
+
+    #[test]
+    fn parses_quorum_rules() {
+        assert_eq!("majority".parse::<QuorumRule>(), Ok(QuorumRule::Majority));
+        assert_eq!(" Majority ".parse::<QuorumRule>(), Ok(QuorumRule::Majority));
+        assert_eq!("2-of-3".parse::<QuorumRule>(), Ok(QuorumRule::NOfM { n: 2, m: 3 }));
+        assert_eq!("3-of-3".parse::<QuorumRule>(), Ok(QuorumRule::NOfM { n: 3, m: 3 }));
+        for invalid in ["", "quorum", "2of3", "x-of-3", "2-of-y", "0-of-3", "4-of-3", "-1-of-3"] {
+            assert!(invalid.parse::<QuorumRule>().is_err(), "{} should not parse", invalid);
+        }
+        assert_eq!(QuorumRule::NOfM { n: 2, m: 3 }.to_string(), "2-of-3");
+    }
+
+    #[test]
+    fn majority_follows_the_heavier_side() {
+        let majority = config(QuorumRule::Majority);
+        let votes = [vote(true, None), vote(true, None), vote(false, None)];
+        let verdict = decide(&votes, &majority);
+        assert_eq!(verdict.status, ConsensusStatus::Up);
+        assert_eq!((verdict.up_votes, verdict.down_votes), (2, 1));
+        assert_eq!(verdict.failing_validators, vec![votes[2].validator_id.unwrap()]);
+        assert_eq!(verdict.error_reasons, vec!["Timeout".to_string()]);
+
+        let tie = [vote(true, None), vote(false, None)];
+        assert_eq!(decide(&tie, &majority).status, ConsensusStatus::Inconclusive);
+        assert_eq!(decide(&[], &majority).status, ConsensusStatus::Inconclusive);
+
+        let mut weighted = [vote(true, None), vote(false, None), vote(false, None)];
+        weighted[0].weight = 3.0;
+        assert_eq!(decide(&weighted, &majority).status, ConsensusStatus::Up);
+    }
+
+    #[test]
+    fn n_of_m_needs_m_votes_and_prefers_down() {
+        let rule = config(QuorumRule::NOfM { n: 2, m: 3 });
+        assert_eq!(
+            decide(&[vote(true, None), vote(true, None)], &rule).status,
+            ConsensusStatus::Inconclusive
+        );
+        assert_eq!(
+            decide(&[vote(true, None), vote(true, None), vote(false, None)], &rule).status,
+            ConsensusStatus::Up
+        );
+        assert_eq!(
+            decide(&[vote(true, None), vote(false, None), vote(false, None)], &rule).status,
+            ConsensusStatus::Down
+        );
+
+        let even = config(QuorumRule::NOfM { n: 2, m: 4 });
+        let split = [vote(true, None), vote(true, None), vote(false, None), vote(false, None)];
+        assert_eq!(decide(&split, &even).status, ConsensusStatus::Down);
+    }
+
+    #[test]
+    fn down_from_too_few_locations_is_degraded() {
+        let rule = ConsensusConfig {
+            min_locations: 2,
+            ..ConsensusConfig::default()
+        };
+        let one_location = [vote(false, Some("eu")), vote(false, Some("eu")), vote(true, Some("us"))];
+        let verdict = decide(&one_location, &rule);
+        assert_eq!(verdict.status, ConsensusStatus::Degraded);
+        assert_eq!(verdict.locations, 2);
+
+        let two_locations = [vote(false, Some("eu")), vote(false, Some("us")), vote(true, Some("us"))];
+        assert_eq!(decide(&two_locations, &rule).status, ConsensusStatus::Down);
+    }
+
+    #[test]
+    fn median_latency_of_up_votes() {
+        let mut votes = [vote(true, None), vote(true, None), vote(true, None), vote(false, None)];
+        votes[0].latency = 300.0;
+        votes[1].latency = 100.0;
+        votes[2].latency = 200.0;
+        assert_eq!(decide(&votes, &ConsensusConfig::default()).latency, Some(200.0));
+        assert_eq!(decide(&[vote(false, None)], &ConsensusConfig::default()).latency, None);
+    }
+
+    #[test]
+    fn heartbeats_decide_on_their_single_vote() {
+        let strict = ConsensusConfig {
+            rule: QuorumRule::NOfM { n: 2, m: 3 },
+            min_locations: 2,
+            reputation_weighting: true,
+        };
+        let missed = [Vote {
+            validator_id: None,
+            location: None,
+            up: false,
+            latency: 0.0,
+            reason: Some("Missed".to_string()),
+            weight: 1.0,
+        }];
+        assert_eq!(decide(&missed, &strict).status, ConsensusStatus::Inconclusive);
+        assert_eq!(decide(&missed, &strict.for_heartbeats()).status, ConsensusStatus::Down);
+        let ping = [Vote {
+            up: true,
+            reason: None,
+            ..missed[0].clone()
+        }];
+        assert_eq!(decide(&ping, &strict.for_heartbeats()).status, ConsensusStatus::Up);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod consensus;
+pub mod reputation;
+pub mod scheduler;
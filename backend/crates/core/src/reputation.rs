@@ -0,0 +1,58 @@
+/// Share of the score carried by each component.
+const AGREEMENT_WEIGHT: f64 = 0.6;
+const RESPONSIVENESS_WEIGHT: f64 = 0.25;
+const LATENCY_WEIGHT: f64 = 0.15;
+
+/// Score of a component without any observations. Each observation moves
+/// the component away from it as if `PRIOR_OBSERVATIONS` neutral ones had
+/// been seen already, so a handful of reports cannot swing a score.
+pub const NEUTRAL_SCORE: f64 = 0.5;
+const PRIOR_OBSERVATIONS: f64 = 10.0;
+
+/// What a validator did over a scoring window.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReputationInputs {
+    /// Checks the scheduler assigned to the validator.
+    pub expected_rounds: u64,
+    /// Assigned checks the validator reported in.
+    pub answered_rounds: u64,
+    /// Reports that matched a conclusive up or down verdict.
+    pub agreed: u64,
+    /// Reports that contradicted a conclusive up or down verdict.
+    pub disagreed: u64,
+    /// Successful reports whose latency could be compared with the round.
+    pub latency_samples: u64,
+    /// Of those, reports with a latency far away from the round median.
+    pub implausible_latencies: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reputation {
+    /// Weighted combination of the components, in `[0, 1]`.
+    pub score: f64,
+    pub agreement: f64,
+    pub responsiveness: f64,
+    pub latency_plausibility: f64,
+}
+
+fn smoothed(successes: u64, total: u64) -> f64 {
+    let successes = successes.min(total) as f64;
+    (successes + NEUTRAL_SCORE * PRIOR_OBSERVATIONS) / (total as f64 + PRIOR_OBSERVATIONS)
+}
+
+pub fn score(inputs: &ReputationInputs) -> Reputation {
+    let agreement = smoothed(inputs.agreed, inputs.agreed + inputs.disagreed);
+    let responsiveness = smoothed(inputs.answered_rounds, inputs.expected_rounds.max(inputs.answered_rounds));
+    let latency_plausibility = smoothed(
+        inputs.latency_samples.saturating_sub(inputs.implausible_latencies),
+        inputs.latency_samples,
+    );
+    Reputation {
+        score: AGREEMENT_WEIGHT * agreement
+            + RESPONSIVENESS_WEIGHT * responsiveness
+            + LATENCY_WEIGHT * latency_plausibility,
+        agreement,
+        responsiveness,
+        latency_plausibility,
+    }
+}
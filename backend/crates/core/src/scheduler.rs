@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDateTime};
+use uuid::Uuid;
+
+/// A connected validator that can be given checks.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub validator_id: Uuid,
+    /// Validator location; `None` when it is not known.
+    pub location: Option<String>,
+    pub reputation: f64,
+    /// Checks the validator has not answered yet.
+    pub load: usize,
+}
+
+/// The validators that check a website in one round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub website_id: Uuid,
+    pub validator_ids: Vec<Uuid>,
+}
+
+/// Picks up to `per_check` validators for each website, in the order given.
+/// Each pick prefers, in turn: a location the website has no validator in
+/// yet, the lowest load (counting the checks assigned so far), the highest
+/// reputation, and finally the lowest id, so plans are deterministic.
+pub fn assign(website_ids: &[Uuid], candidates: &[Candidate], per_check: usize) -> Vec<Assignment> {
+    let mut load: Vec<usize> = candidates.iter().map(|candidate| candidate.load).collect();
+    let picks = per_check.min(candidates.len());
+
+    website_ids
+        .iter()
+        .map(|&website_id| {
+            let mut chosen: Vec<usize> = Vec::with_capacity(picks);
+            let mut covered: HashSet<&str> = HashSet::new();
+            while chosen.len() < picks {
+                let new_location = |i: usize| {
+                    candidates[i]
+                        .location
+                        .as_deref()
+                        .is_some_and(|location| !covered.contains(location))
+                };
+                let Some(best) = (0..candidates.len())
+                    .filter(|i| !chosen.contains(i))
+                    .min_by(|&a, &b| {
+                        new_location(b)
+                            .cmp(&new_location(a))
+                            .then(load[a].cmp(&load[b]))
+                            .then(candidates[b].reputation.total_cmp(&candidates[a].reputation))
+                            .then(candidates[a].validator_id.cmp(&candidates[b].validator_id))
+                    })
+                else {
+                    break;
+                };
+                if let Some(location) = candidates[best].location.as_deref() {
+                    covered.insert(location);
+                }
+                load[best] += 1;
+                chosen.push(best);
+            }
+            Assignment {
+                website_id,
+                validator_ids: chosen.into_iter().map(|i| candidates[i].validator_id).collect(),
+            }
+        })
+        .collect()
+}
+
+/// When a website that was due at `due_at` and is checked at `now` is due
+/// again: one interval after `due_at`, so checks don't drift, unless the
+/// website has fallen more than an interval behind, e.g. while no validator
+/// was connected; then one interval from now.
+pub fn next_check_at(due_at: NaiveDateTime, interval_secs: i64, now: NaiveDateTime) -> NaiveDateTime {
+    let interval = Duration::seconds(interval_secs);
+    let next = due_at + interval;
+    if next <= now { now + interval } else { next }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn id(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    fn candidate(n: u128, location: Option<&str>, reputation: f64, load: usize) -> Candidate {
+        Candidate {
+            validator_id: id(n),
+            location: location.map(str::to_string),
+            reputation,
+            load,
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn prefers_new_locations_over_load() {
+        let candidates = [
+            candidate(1, Some("eu"), 0.5, 0),
+            candidate(2, Some("eu"), 0.5, 0),
+            candidate(3, Some("us"), 0.5, 5),
+        ];
+        let plan = assign(&[id(10)], &candidates, 2);
+        assert_eq!(plan[0].validator_ids, vec![id(1), id(3)]);
+    }
+
+    #[test]
+    fn prefers_lower_load_then_reputation_then_id() {
+        let candidates = [
+            candidate(1, None, 0.9, 2),
+            candidate(2, None, 0.1, 0),
+            candidate(3, None, 0.5, 1),
+            candidate(4, None, 0.9, 1),
+            candidate(5, None, 0.5, 1),
+        ];
+        let plan = assign(&[id(10)], &candidates, 4);
+        assert_eq!(plan[0].validator_ids, vec![id(2), id(4), id(3), id(5)]);
+    }
+
+    #[test]
+    fn counts_picks_towards_load_across_websites() {
+        let candidates = [candidate(1, None, 0.5, 0), candidate(2, None, 0.5, 0)];
+        let plan = assign(&[id(10), id(11)], &candidates, 1);
+        assert_eq!(plan[0].validator_ids, vec![id(1)]);
+        assert_eq!(plan[1].validator_ids, vec![id(2)]);
+    }
+
+    #[test]
+    fn clamps_picks_to_the_candidates() {
+        let candidates = [candidate(1, Some("eu"), 0.5, 0), candidate(2, None, 0.5, 0)];
+        let plan = assign(&[id(10)], &candidates, 5);
+        assert_eq!(plan[0].validator_ids, vec![id(1), id(2)]);
+        assert!(assign(&[id(10)], &[], 3)[0].validator_ids.is_empty());
+    }
+
+    #[test]
+    fn next_check_keeps_the_cadence_unless_behind() {
+        assert_eq!(next_check_at(at(12, 0), 300, at(12, 1)), at(12, 5));
+        assert_eq!(next_check_at(at(12, 0), 300, at(12, 5)), at(12, 10));
+        assert_eq!(next_check_at(at(12, 0), 300, at(12, 7)), at(12, 12));
+    }
+}
@@ -0,0 +1,20 @@
+-- Websites can be probed by validators ('http') or pushed to by a job ('heartbeat')
+ALTER TABLE websites ADD COLUMN "monitor_type" TEXT NOT NULL DEFAULT 'http';
+
+-- Heartbeat ticks are recorded by the backend itself, not by a validator
+ALTER TABLE website_ticks ALTER COLUMN "validator_id" DROP NOT NULL;
+
+-- Create HeartbeatMonitors table
+CREATE TABLE heartbeat_monitors (
+    "website_id" UUID PRIMARY KEY,
+    "token" TEXT NOT NULL,
+    "period_seconds" INTEGER NOT NULL,
+    "grace_seconds" INTEGER NOT NULL DEFAULT 0,
+    "last_ping_at" TIMESTAMP,
+    "last_started_at" TIMESTAMP,
+    "next_due_at" TIMESTAMP NOT NULL,
+    CONSTRAINT "heartbeat_monitors_token_unique" UNIQUE ("token"),
+    CONSTRAINT "fk_heartbeat_monitors_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_heartbeat_monitors_next_due_at" ON "heartbeat_monitors"("next_due_at");
@@ -0,0 +1,23 @@
+-- Create WebsiteGroups table
+CREATE TABLE website_groups (
+    "id" UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    "user_id" UUID NOT NULL,
+    "name" TEXT NOT NULL,
+    CONSTRAINT "website_groups_user_id_name_unique" UNIQUE ("user_id", "name"),
+    CONSTRAINT "fk_website_groups_user_id" FOREIGN KEY ("user_id") REFERENCES "users"("id") ON DELETE CASCADE
+);
+
+-- A website belongs to at most one group
+ALTER TABLE websites ADD COLUMN "group_id" UUID;
+ALTER TABLE websites ADD CONSTRAINT "fk_websites_group_id" FOREIGN KEY ("group_id") REFERENCES "website_groups"("id") ON DELETE SET NULL;
+
+-- Create WebsiteTags table
+CREATE TABLE website_tags (
+    "website_id" UUID NOT NULL,
+    "tag" TEXT NOT NULL,
+    PRIMARY KEY ("website_id", "tag"),
+    CONSTRAINT "fk_website_tags_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_websites_group_id" ON "websites"("group_id");
+CREATE INDEX "idx_website_tags_tag" ON "website_tags"("tag");
@@ -0,0 +1,2 @@
+-- Serves "latest N ticks of a website" lookups without a sort
+CREATE INDEX "idx_website_ticks_website_id_created_at" ON "website_ticks"("website_id", "created_at" DESC);
@@ -0,0 +1,12 @@
+-- Create MaintenanceWindows table; ticks inside a window don't count towards uptime
+CREATE TABLE maintenance_windows (
+    "id" UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    "website_id" UUID NOT NULL,
+    "starts_at" TIMESTAMP NOT NULL,
+    "ends_at" TIMESTAMP NOT NULL,
+    "reason" TEXT,
+    CONSTRAINT "maintenance_windows_range_check" CHECK ("ends_at" > "starts_at"),
+    CONSTRAINT "fk_maintenance_windows_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_maintenance_windows_website_id" ON "maintenance_windows"("website_id", "starts_at");
@@ -0,0 +1,45 @@
+-- Hourly and daily aggregates of website_ticks, per website and validator.
+-- Latency columns only cover successful ticks. latency_sketch maps a
+-- log-scale bucket index to a count and can be merged by adding counts.
+-- active_minutes counts the minutes in which the website got any tick, so it
+-- is the same on every validator row of a website and bucket.
+CREATE TABLE tick_rollups_hourly (
+    "website_id" UUID NOT NULL,
+    "validator_id" UUID,
+    "bucket_start" TIMESTAMP NOT NULL,
+    "up_count" INTEGER NOT NULL,
+    "down_count" INTEGER NOT NULL,
+    "active_minutes" INTEGER NOT NULL,
+    "latency_sum" FLOAT NOT NULL,
+    "latency_min" FLOAT,
+    "latency_max" FLOAT,
+    "latency_sketch" JSONB NOT NULL DEFAULT '{}',
+    CONSTRAINT "fk_tick_rollups_hourly_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_tick_rollups_hourly_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
+
+CREATE TABLE tick_rollups_daily (
+    "website_id" UUID NOT NULL,
+    "validator_id" UUID,
+    "bucket_start" TIMESTAMP NOT NULL,
+    "up_count" INTEGER NOT NULL,
+    "down_count" INTEGER NOT NULL,
+    "active_minutes" INTEGER NOT NULL,
+    "latency_sum" FLOAT NOT NULL,
+    "latency_min" FLOAT,
+    "latency_max" FLOAT,
+    "latency_sketch" JSONB NOT NULL DEFAULT '{}',
+    CONSTRAINT "fk_tick_rollups_daily_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_tick_rollups_daily_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_tick_rollups_hourly_website_id_bucket_start" ON "tick_rollups_hourly"("website_id", "bucket_start");
+CREATE INDEX "idx_tick_rollups_hourly_bucket_start" ON "tick_rollups_hourly"("bucket_start");
+CREATE INDEX "idx_tick_rollups_daily_website_id_bucket_start" ON "tick_rollups_daily"("website_id", "bucket_start");
+CREATE INDEX "idx_tick_rollups_daily_bucket_start" ON "tick_rollups_daily"("bucket_start");
+
+-- Progress of background jobs that work through website_ticks in time order
+CREATE TABLE job_watermarks (
+    "name" TEXT PRIMARY KEY,
+    "watermark" TIMESTAMP NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Per-account retention, overriding TICK_RETENTION_DAYS / ROLLUP_RETENTION_DAYS
+CREATE TABLE retention_overrides (
+    "user_id" UUID PRIMARY KEY,
+    "tick_retention_days" INTEGER,
+    "rollup_retention_days" INTEGER,
+    CONSTRAINT "retention_overrides_positive_check" CHECK (
+        ("tick_retention_days" IS NULL OR "tick_retention_days" > 0)
+        AND ("rollup_retention_days" IS NULL OR "rollup_retention_days" > 0)
+    ),
+    CONSTRAINT "fk_retention_overrides_user_id" FOREIGN KEY ("user_id") REFERENCES "users"("id") ON DELETE CASCADE
+);
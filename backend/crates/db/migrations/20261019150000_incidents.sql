@@ -0,0 +1,15 @@
+-- Create Incidents table; an incident is open while resolved_at is NULL
+CREATE TABLE incidents (
+    "id" UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    "website_id" UUID NOT NULL,
+    "started_at" TIMESTAMP NOT NULL,
+    "resolved_at" TIMESTAMP,
+    "duration_seconds" INTEGER,
+    "first_failing_validators" UUID[] NOT NULL DEFAULT '{}',
+    "error_reasons" TEXT[] NOT NULL DEFAULT '{}',
+    CONSTRAINT "fk_incidents_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE
+);
+
+-- At most one open incident per website
+CREATE UNIQUE INDEX "idx_incidents_open_website_id" ON "incidents"("website_id") WHERE "resolved_at" IS NULL;
+CREATE INDEX "idx_incidents_website_id_started_at" ON "incidents"("website_id", "started_at" DESC);
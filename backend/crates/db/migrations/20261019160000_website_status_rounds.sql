@@ -0,0 +1,19 @@
+-- Create Website Status Rounds table; one consensus verdict per website per check round
+CREATE TABLE website_status_rounds (
+    "website_id" UUID NOT NULL,
+    "round_start" TIMESTAMP NOT NULL,
+    "status" TEXT NOT NULL,
+    "up_votes" INTEGER NOT NULL,
+    "down_votes" INTEGER NOT NULL,
+    "locations" INTEGER NOT NULL,
+    "latency" DOUBLE PRECISION,
+    "failing_validators" UUID[] NOT NULL DEFAULT '{}',
+    "error_reasons" TEXT[] NOT NULL DEFAULT '{}',
+    "decided_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "website_status_rounds_pkey" PRIMARY KEY ("website_id", "round_start"),
+    CONSTRAINT "fk_website_status_rounds_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE,
+    CONSTRAINT "website_status_rounds_status_check" CHECK ("status" IN ('up', 'down', 'degraded', 'inconclusive'))
+);
+
+-- Latest conclusive round per website
+CREATE INDEX "idx_website_status_rounds_conclusive" ON "website_status_rounds"("website_id", "round_start" DESC) WHERE "status" <> 'inconclusive';
@@ -0,0 +1,18 @@
+-- Current reputation of each validator, see validator_reputation_history
+ALTER TABLE validators ADD COLUMN "reputation" DOUBLE PRECISION NOT NULL DEFAULT 0.5;
+
+-- Create Validator Reputation History table; one row per validator per scoring run
+CREATE TABLE validator_reputation_history (
+    "validator_id" UUID NOT NULL,
+    "computed_at" TIMESTAMP NOT NULL,
+    "score" DOUBLE PRECISION NOT NULL,
+    "agreement" DOUBLE PRECISION NOT NULL,
+    "responsiveness" DOUBLE PRECISION NOT NULL,
+    "latency_plausibility" DOUBLE PRECISION NOT NULL,
+    "expected_rounds" INTEGER NOT NULL,
+    "answered_rounds" INTEGER NOT NULL,
+    "agreed" INTEGER NOT NULL,
+    "disagreed" INTEGER NOT NULL,
+    CONSTRAINT "validator_reputation_history_pkey" PRIMARY KEY ("validator_id", "computed_at"),
+    CONSTRAINT "fk_validator_reputation_history_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- GeoIP details of each validator's IP; location holds the country code once resolved
+ALTER TABLE validators
+    ADD COLUMN "country" TEXT,
+    ADD COLUMN "region" TEXT,
+    ADD COLUMN "city" TEXT,
+    ADD COLUMN "latitude" DOUBLE PRECISION,
+    ADD COLUMN "longitude" DOUBLE PRECISION,
+    ADD COLUMN "asn" BIGINT,
+    ADD COLUMN "as_organization" TEXT,
+    ADD COLUMN "geo_resolved_at" TIMESTAMP;
+
+CREATE INDEX "idx_validators_geo_unresolved" ON "validators"("id") WHERE "geo_resolved_at" IS NULL;
@@ -0,0 +1,15 @@
+-- Details of each check: HTTP status, failure class, response size and the
+-- timing breakdown in milliseconds. All are NULL for ticks that predate them
+-- and for heartbeat ticks.
+ALTER TABLE website_ticks
+    ADD COLUMN "http_status_code" INTEGER,
+    ADD COLUMN "error_class" TEXT,
+    ADD COLUMN "response_size_bytes" BIGINT,
+    ADD COLUMN "dns_ms" DOUBLE PRECISION,
+    ADD COLUMN "connect_ms" DOUBLE PRECISION,
+    ADD COLUMN "tls_ms" DOUBLE PRECISION,
+    ADD COLUMN "ttfb_ms" DOUBLE PRECISION,
+    ADD CONSTRAINT "website_ticks_http_status_code_check" CHECK ("http_status_code" BETWEEN 100 AND 599),
+    ADD CONSTRAINT "website_ticks_error_class_check" CHECK ("error_class" IN ('dns_failure', 'connect_refused', 'tls_error', 'timeout', 'assertion_failed')),
+    ADD CONSTRAINT "website_ticks_response_size_bytes_check" CHECK ("response_size_bytes" >= 0),
+    ADD CONSTRAINT "website_ticks_timings_check" CHECK ("dns_ms" >= 0 AND "connect_ms" >= 0 AND "tls_ms" >= 0 AND "ttfb_ms" >= 0);
@@ -0,0 +1,14 @@
+-- Dispatch round of each tick; a validator reports once per website and round
+ALTER TABLE website_ticks ADD COLUMN "round_id" UUID;
+CREATE UNIQUE INDEX "idx_website_ticks_validator_id_website_id_round_id" ON "website_ticks"("validator_id", "website_id", "round_id") WHERE "round_id" IS NOT NULL;
+
+-- Create Validator Nonces table; nonces of accepted reports, kept until the report would be stale anyway
+CREATE TABLE validator_nonces (
+    "validator_id" UUID NOT NULL,
+    "nonce" TEXT NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    CONSTRAINT "validator_nonces_pkey" PRIMARY KEY ("validator_id", "nonce"),
+    CONSTRAINT "fk_validator_nonces_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_validator_nonces_expires_at" ON "validator_nonces"("expires_at");
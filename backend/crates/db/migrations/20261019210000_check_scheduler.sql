@@ -0,0 +1,20 @@
+-- How often each website is checked, and when it is next due
+ALTER TABLE websites ADD COLUMN "check_interval_secs" INTEGER NOT NULL DEFAULT 60;
+ALTER TABLE websites ADD COLUMN "next_check_at" TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc');
+ALTER TABLE websites ADD CONSTRAINT "websites_check_interval_secs_check" CHECK ("check_interval_secs" >= 60);
+
+CREATE INDEX "idx_websites_next_check_at" ON "websites"("next_check_at") WHERE "disabled" = false;
+
+-- Create Check Assignments table; the validators the scheduler sent a website to in a round
+CREATE TABLE check_assignments (
+    "round_id" UUID NOT NULL,
+    "website_id" UUID NOT NULL,
+    "validator_id" UUID NOT NULL,
+    "assigned_at" TIMESTAMP NOT NULL,
+    CONSTRAINT "check_assignments_pkey" PRIMARY KEY ("round_id", "website_id", "validator_id"),
+    CONSTRAINT "fk_check_assignments_website_id" FOREIGN KEY ("website_id") REFERENCES "websites"("id") ON DELETE CASCADE,
+    CONSTRAINT "fk_check_assignments_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_check_assignments_website_id_assigned_at" ON "check_assignments"("website_id", "assigned_at" DESC);
+CREATE INDEX "idx_check_assignments_assigned_at" ON "check_assignments"("assigned_at");
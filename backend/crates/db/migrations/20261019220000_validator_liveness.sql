@@ -0,0 +1,13 @@
+-- Liveness of each validator, kept current by its heartbeats
+ALTER TABLE validators ADD COLUMN "online" BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE validators ADD COLUMN "last_seen_at" TIMESTAMP;
+
+-- Create Validator Presence table; one row per validator per minute it sent a heartbeat in
+CREATE TABLE validator_presence (
+    "validator_id" UUID NOT NULL,
+    "minute" TIMESTAMP NOT NULL,
+    CONSTRAINT "validator_presence_pkey" PRIMARY KEY ("validator_id", "minute"),
+    CONSTRAINT "fk_validator_presence_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_validator_presence_minute" ON "validator_presence"("minute");
@@ -0,0 +1,8 @@
+-- Onboarding lifecycle of each validator; only active validators get work and payouts
+ALTER TABLE validators ADD COLUMN "status" TEXT NOT NULL DEFAULT 'pending';
+ALTER TABLE validators ADD COLUMN "status_reason" TEXT;
+ALTER TABLE validators ADD COLUMN "status_changed_at" TIMESTAMP;
+ALTER TABLE validators ADD CONSTRAINT "validators_status_check" CHECK ("status" IN ('pending', 'active', 'suspended', 'banned'));
+
+-- Validators that signed up before onboarding existed stay in service
+UPDATE validators SET "status" = 'active', "status_changed_at" = (now() AT TIME ZONE 'utc');
@@ -0,0 +1,26 @@
+-- Create Payouts table; one row per payout, written before its transfer is submitted
+CREATE TABLE payouts (
+    "id" UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    "validator_id" UUID NOT NULL,
+    -- Pending payouts taken from the validator, given back if the payout fails
+    "credits" INTEGER NOT NULL,
+    -- Lamports transferred, after reputation weighting
+    "amount" BIGINT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'created',
+    "signature" TEXT,
+    "recent_blockhash" TEXT,
+    "error" TEXT,
+    "created_at" TIMESTAMP NOT NULL,
+    "submitted_at" TIMESTAMP,
+    "resolved_at" TIMESTAMP,
+    CONSTRAINT "fk_payouts_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE,
+    CONSTRAINT "payouts_status_check" CHECK ("status" IN ('created', 'submitted', 'confirmed', 'failed')),
+    CONSTRAINT "payouts_amount_check" CHECK ("credits" > 0 AND "amount" > 0),
+    CONSTRAINT "payouts_signature_check" CHECK ("status" IN ('created', 'failed') OR "signature" IS NOT NULL),
+    CONSTRAINT "payouts_signature_unique" UNIQUE ("signature")
+);
+
+-- At most one payout per validator is in flight, so retries find it
+CREATE UNIQUE INDEX "idx_payouts_validator_id_open" ON "payouts"("validator_id") WHERE "status" IN ('created', 'submitted');
+CREATE INDEX "idx_payouts_validator_id_created_at" ON "payouts"("validator_id", "created_at" DESC);
+CREATE INDEX "idx_payouts_status" ON "payouts"("status") WHERE "status" IN ('created', 'submitted');
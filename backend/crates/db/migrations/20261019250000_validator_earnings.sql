@@ -0,0 +1,11 @@
+-- Create Validator Earnings table; what each validator was credited per day, kept beyond tick retention
+CREATE TABLE validator_earnings_daily (
+    "validator_id" UUID NOT NULL,
+    "day" DATE NOT NULL,
+    "ticks" INTEGER NOT NULL DEFAULT 0,
+    "credits" BIGINT NOT NULL DEFAULT 0,
+    CONSTRAINT "validator_earnings_daily_pkey" PRIMARY KEY ("validator_id", "day"),
+    CONSTRAINT "fk_validator_earnings_daily_validator_id" FOREIGN KEY ("validator_id") REFERENCES "validators"("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "idx_payouts_validator_id_resolved_at" ON "payouts"("validator_id", "resolved_at") WHERE "status" = 'confirmed';
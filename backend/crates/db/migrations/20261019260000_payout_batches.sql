@@ -0,0 +1,16 @@
+-- Create Payout Batches table; one row per batch run, whose payouts share transactions
+CREATE TABLE payout_batches (
+    "id" UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Pending payouts a validator needed to be included
+    "threshold" INTEGER NOT NULL,
+    "created_at" TIMESTAMP NOT NULL,
+    "completed_at" TIMESTAMP
+);
+
+ALTER TABLE payouts ADD COLUMN "batch_id" UUID;
+ALTER TABLE payouts ADD CONSTRAINT "fk_payouts_batch_id" FOREIGN KEY ("batch_id") REFERENCES "payout_batches"("id") ON DELETE SET NULL;
+
+-- Payouts of one batch share the signature of the transaction carrying them
+ALTER TABLE payouts DROP CONSTRAINT "payouts_signature_unique";
+CREATE INDEX "idx_payouts_signature" ON "payouts"("signature");
+CREATE INDEX "idx_payouts_batch_id" ON "payouts"("batch_id");
@@ -0,0 +1,31 @@
+-- Lamports a batch could transfer in all, when capped
+ALTER TABLE payout_batches ADD COLUMN "spend_cap" BIGINT;
+
+-- Create Payout Settings table; a single row holding the payout kill switch
+CREATE TABLE payout_settings (
+    "id" BOOLEAN PRIMARY KEY DEFAULT TRUE,
+    "paused" BOOLEAN NOT NULL DEFAULT FALSE,
+    "paused_reason" TEXT,
+    "changed_at" TIMESTAMP,
+    CONSTRAINT "payout_settings_single_row_check" CHECK ("id")
+);
+
+INSERT INTO payout_settings ("id") VALUES (TRUE);
+
+-- Create Payout Runs table; the report of each scheduled payout run
+CREATE TABLE payout_runs (
+    "id" UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    "status" TEXT NOT NULL,
+    "threshold" INTEGER NOT NULL,
+    "spend_cap" BIGINT NOT NULL,
+    "batch_id" UUID,
+    -- Validators owed the threshold but left for a later run by the spending cap
+    "deferred" INTEGER NOT NULL DEFAULT 0,
+    "error" TEXT,
+    "started_at" TIMESTAMP NOT NULL,
+    "finished_at" TIMESTAMP NOT NULL,
+    CONSTRAINT "fk_payout_runs_batch_id" FOREIGN KEY ("batch_id") REFERENCES "payout_batches"("id") ON DELETE SET NULL,
+    CONSTRAINT "payout_runs_status_check" CHECK ("status" IN ('sent', 'idle', 'paused', 'failed'))
+);
+
+CREATE INDEX "idx_payout_runs_started_at" ON "payout_runs"("started_at" DESC);
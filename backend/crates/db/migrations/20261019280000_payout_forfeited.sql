@@ -0,0 +1,3 @@
+-- Credits a payout took from the validator but didn't transfer because of reputation weighting
+ALTER TABLE payouts ADD COLUMN "forfeited" BIGINT GENERATED ALWAYS AS ("credits" - "amount") STORED;
+ALTER TABLE payouts ADD CONSTRAINT "payouts_forfeited_check" CHECK ("amount" <= "credits");
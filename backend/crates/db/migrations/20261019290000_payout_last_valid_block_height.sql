@@ -0,0 +1,2 @@
+-- Last block height at which a payout's transaction can still land; past it, at finalized commitment, the transfer has expired
+ALTER TABLE payouts ADD COLUMN "last_valid_block_height" BIGINT;
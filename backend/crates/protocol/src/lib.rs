@@ -0,0 +1,8 @@
+//! Messages exchanged between the hub and validators, and the tick reports
+//! validators sign. The TypeScript in `hub/types.ts` and
+//! `validator/types.ts` mirrors these types.
+
+pub mod messages;
+pub mod negotiation;
+pub mod schema;
+pub mod tick;
@@ -0,0 +1,240 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{negotiation::Capability, tick::TickReport};
+
+/// First message of a validator after receiving its challenge.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SignupIncomingMessage {
+    /// Highest protocol version the validator speaks.
+    pub protocol_version: u16,
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+    pub ip: String,
+    /// Base58 ed25519 public key.
+    pub public_key: String,
+    /// JSON array of the signature bytes over `signed_text`.
+    pub signed_message: String,
+    pub callback_id: Uuid,
+    /// Copied from the `ChallengeOutgoingMessage` of this connection.
+    pub challenge: String,
+}
+
+impl SignupIncomingMessage {
+    /// Text the validator signs to prove it holds `public_key`. The
+    /// challenge ties the signature to one connection.
+    pub fn signed_text(&self) -> String {
+        format!("Signed message for {}, {}, {}", self.callback_id, self.public_key, self.challenge)
+    }
+}
+
+/// Answer of a validator to a `ValidateOutgoingMessage`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateIncomingMessage {
+    pub callback_id: Uuid,
+    /// JSON array of the signature bytes over `signed_text`.
+    pub signed_message: String,
+    pub validator_id: Uuid,
+    pub report: TickReport,
+}
+
+impl ValidateIncomingMessage {
+    pub fn signed_text(&self) -> String {
+        format!("Replying to {}", self.callback_id)
+    }
+}
+
+/// First message on a new connection; the validator signs it into its signup.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeOutgoingMessage {
+    pub challenge: String,
+}
+
+/// Reply to a signup with the id the validator reports under and what was
+/// negotiated.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SignupOutgoingMessage {
+    pub validator_id: Uuid,
+    pub callback_id: Uuid,
+    pub protocol_version: u16,
+    pub capabilities: Vec<Capability>,
+}
+
+/// Asks a validator to check a website.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateOutgoingMessage {
+    pub url: String,
+    pub callback_id: Uuid,
+    pub website_id: Uuid,
+    /// Dispatch round, copied into the report.
+    pub round_id: Uuid,
+}
+
+/// Sent instead of an answer when a message is rejected, e.g. a signup
+/// with an unsupported protocol version.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorOutgoingMessage {
+    /// Callback of the rejected message, when it could be read.
+    pub callback_id: Option<Uuid>,
+    pub message: String,
+}
+
+/// Validator to hub.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum IncomingMessage {
+    Signup(SignupIncomingMessage),
+    Validate(Box<ValidateIncomingMessage>),
+    /// Sent every `HEARTBEAT_INTERVAL_SECS` after signup; a validator that
+    /// stays silent for longer is considered offline.
+    Heartbeat,
+}
+
+/// How often a signed-up validator sends a heartbeat.
+pub const HEARTBEAT_INTERVAL_SECS: u64 = 30;
+
+/// Hub to validator.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum OutgoingMessage {
+    Challenge(ChallengeOutgoingMessage),
+    Signup(SignupOutgoingMessage),
+    Validate(ValidateOutgoingMessage),
+    Error(ErrorOutgoingMessage),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Reads `sent` as `T` and writes it back, which must give the same JSON.
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(sent: Value) -> T {
+        let message: T = serde_json::from_value(sent.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&message).unwrap(), sent);
+        message
+    }
+
+    // The messages below are written as `validator/index.ts` and
+    // `hub/index.ts` send them.
+
+    #[test]
+    fn reads_signup_from_the_validator() {
+        let message = round_trip::<IncomingMessage>(json!({
+            "type": "signup",
+            "data": {
+                "protocolVersion": 2,
+                "capabilities": ["http", "timings"],
+                "callbackId": "00000000-0000-0000-0000-0000000000cc",
+                "challenge": "4f1c2a",
+                "ip": "127.0.0.1",
+                "publicKey": "8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR",
+                "signedMessage": "[1,2,3]"
+            }
+        }));
+        let IncomingMessage::Signup(signup) = message else { panic!("not a signup") };
+        assert_eq!(
+            signup.signed_text(),
+            "Signed message for 00000000-0000-0000-0000-0000000000cc, \
+             8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR, 4f1c2a"
+        );
+    }
+
+    #[test]
+    fn reads_signup_without_capabilities() {
+        let message: IncomingMessage = serde_json::from_value(json!({
+            "type": "signup",
+            "data": {
+                "protocolVersion": 2,
+                "callbackId": "00000000-0000-0000-0000-0000000000cc",
+                "challenge": "4f1c2a",
+                "ip": "127.0.0.1",
+                "publicKey": "8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR",
+                "signedMessage": "[1,2,3]"
+            }
+        }))
+        .unwrap();
+        let IncomingMessage::Signup(signup) = message else { panic!("not a signup") };
+        assert!(signup.capabilities.is_empty());
+    }
+
+    #[test]
+    fn reads_validate_from_the_validator() {
+        let message = round_trip::<IncomingMessage>(json!({
+            "type": "validate",
+            "data": {
+                "callbackId": "00000000-0000-0000-0000-0000000000cc",
+                "validatorId": "00000000-0000-0000-0000-0000000000dd",
+                "signedMessage": "[1,2,3]",
+                "report": {
+                    "websiteId": "00000000-0000-0000-0000-0000000000aa",
+                    "validatorId": "00000000-0000-0000-0000-0000000000dd",
+                    "roundId": "00000000-0000-0000-0000-0000000000ee",
+                    "status": "Bad",
+                    "latency": 182.5,
+                    "timestamp": 1760000000000i64,
+                    "nonce": "c0ffee00c0ffee00c0ffee00",
+                    "httpStatusCode": 503,
+                    "errorClass": "assertion_failed",
+                    "responseSizeBytes": 1024,
+                    "timings": { "dnsMs": null, "connectMs": null, "tlsMs": null, "ttfbMs": 150.25 },
+                    "signature": "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW"
+                }
+            }
+        }));
+        let IncomingMessage::Validate(validate) = message else { panic!("not a validate") };
+        assert_eq!(validate.signed_text(), "Replying to 00000000-0000-0000-0000-0000000000cc");
+        assert_eq!(validate.report.error_class, Some(crate::tick::ErrorClass::AssertionFailed));
+    }
+
+    #[test]
+    fn reads_heartbeat_without_data() {
+        let message = round_trip::<IncomingMessage>(json!({ "type": "heartbeat" }));
+        assert!(matches!(message, IncomingMessage::Heartbeat));
+    }
+
+    #[test]
+    fn writes_every_message_of_the_hub() {
+        round_trip::<OutgoingMessage>(json!({
+            "type": "challenge",
+            "data": { "challenge": "4f1c2a" }
+        }));
+        round_trip::<OutgoingMessage>(json!({
+            "type": "signup",
+            "data": {
+                "validatorId": "00000000-0000-0000-0000-0000000000dd",
+                "callbackId": "00000000-0000-0000-0000-0000000000cc",
+                "protocolVersion": 2,
+                "capabilities": ["timings"]
+            }
+        }));
+        round_trip::<OutgoingMessage>(json!({
+            "type": "validate",
+            "data": {
+                "url": "https://example.com",
+                "callbackId": "00000000-0000-0000-0000-0000000000cc",
+                "websiteId": "00000000-0000-0000-0000-0000000000aa",
+                "roundId": "00000000-0000-0000-0000-0000000000ee"
+            }
+        }));
+        round_trip::<OutgoingMessage>(json!({
+            "type": "error",
+            "data": {
+                "callbackId": "00000000-0000-0000-0000-0000000000cc",
+                "message": "protocol version 1 is not supported, expected 2 to 2"
+            }
+        }));
+        round_trip::<OutgoingMessage>(json!({
+            "type": "error",
+            "data": { "callbackId": null, "message": "Sign up first" }
+        }));
+    }
+}
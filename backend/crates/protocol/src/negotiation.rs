@@ -0,0 +1,113 @@
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Version spoken by this side of the connection.
+pub const PROTOCOL_VERSION: u16 = 2;
+/// Oldest version still accepted at signup. Version 1 signups carried no
+/// challenge and could be replayed.
+pub const MIN_PROTOCOL_VERSION: u16 = 2;
+
+/// Something a validator can do beyond the base protocol, announced at signup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Runs HTTP checks.
+    Http,
+    /// Reports DNS, connect, TLS and time-to-first-byte timings.
+    Timings,
+    /// A capability this side does not know; never negotiated.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Capabilities this side can make use of.
+pub const SUPPORTED_CAPABILITIES: [Capability; 2] = [Capability::Http, Capability::Timings];
+
+/// What both sides agreed on at signup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiated {
+    pub protocol_version: u16,
+    pub capabilities: Vec<Capability>,
+}
+
+impl Negotiated {
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedVersion(pub u16);
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "protocol version {} is not supported, expected {} to {}",
+            self.0, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedVersion {}
+
+/// Settles on the highest version both sides speak and the capabilities
+/// both sides know. A validator newer than us is talked to in our version.
+pub fn negotiate(version: u16, offered: &[Capability]) -> Result<Negotiated, UnsupportedVersion> {
+    if version < MIN_PROTOCOL_VERSION {
+        return Err(UnsupportedVersion(version));
+    }
+    Ok(Negotiated {
+        protocol_version: version.min(PROTOCOL_VERSION),
+        capabilities: SUPPORTED_CAPABILITIES
+            .into_iter()
+            .filter(|capability| offered.contains(capability))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_versions_below_the_floor() {
+        assert_eq!(
+            negotiate(MIN_PROTOCOL_VERSION - 1, &SUPPORTED_CAPABILITIES),
+            Err(UnsupportedVersion(MIN_PROTOCOL_VERSION - 1))
+        );
+        assert_eq!(negotiate(0, &[]), Err(UnsupportedVersion(0)));
+    }
+
+    #[test]
+    fn talks_to_newer_validators_in_our_version() {
+        let negotiated = negotiate(PROTOCOL_VERSION + 1, &[]).unwrap();
+        assert_eq!(negotiated.protocol_version, PROTOCOL_VERSION);
+        let negotiated = negotiate(MIN_PROTOCOL_VERSION, &[]).unwrap();
+        assert_eq!(negotiated.protocol_version, MIN_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn keeps_only_capabilities_both_sides_know() {
+        let negotiated = negotiate(PROTOCOL_VERSION, &[Capability::Timings]).unwrap();
+        assert_eq!(negotiated.capabilities, [Capability::Timings]);
+        assert!(negotiated.supports(Capability::Timings));
+        assert!(!negotiated.supports(Capability::Http));
+
+        let offered = [Capability::Unknown, Capability::Http, Capability::Timings];
+        let negotiated = negotiate(PROTOCOL_VERSION, &offered).unwrap();
+        assert_eq!(negotiated.capabilities, SUPPORTED_CAPABILITIES);
+        assert!(!negotiated.supports(Capability::Unknown));
+    }
+
+    #[test]
+    fn reads_unknown_capabilities_as_unknown() {
+        let offered: Vec<Capability> =
+            serde_json::from_str(r#"["http", "teleport", "timings"]"#).unwrap();
+        assert_eq!(offered, [Capability::Http, Capability::Unknown, Capability::Timings]);
+        let negotiated = negotiate(PROTOCOL_VERSION, &offered).unwrap();
+        assert_eq!(negotiated.capabilities, SUPPORTED_CAPABILITIES);
+    }
+}
@@ -0,0 +1,60 @@
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::{
+    messages::{IncomingMessage, OutgoingMessage},
+    tick::TickReport,
+};
+
+/// JSON Schemas of everything sent over the wire, keyed by file stem.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("incoming-message", schema_for!(IncomingMessage)),
+        ("outgoing-message", schema_for!(OutgoingMessage)),
+        ("tick-report", schema_for!(TickReport)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Names of the `type` tags a tagged enum schema accepts.
+    fn tags(schema: &RootSchema) -> Vec<String> {
+        let schema = serde_json::to_value(schema).unwrap();
+        schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| variant["properties"]["type"]["enum"][0].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn has_a_schema_for_every_message() {
+        let schemas = schemas();
+        let stems = schemas.iter().map(|(stem, _)| *stem).collect::<Vec<_>>();
+        assert_eq!(stems, ["incoming-message", "outgoing-message", "tick-report"]);
+
+        assert_eq!(tags(&schemas[0].1), ["signup", "validate", "heartbeat"]);
+        assert_eq!(tags(&schemas[1].1), ["challenge", "signup", "validate", "error"]);
+
+        let incoming = serde_json::to_value(&schemas[0].1).unwrap();
+        for definition in ["SignupIncomingMessage", "ValidateIncomingMessage", "TickReport", "Capability"] {
+            assert!(incoming["definitions"][definition].is_object(), "{}", definition);
+        }
+        let outgoing = serde_json::to_value(&schemas[1].1).unwrap();
+        for definition in [
+            "ChallengeOutgoingMessage",
+            "SignupOutgoingMessage",
+            "ValidateOutgoingMessage",
+            "ErrorOutgoingMessage",
+        ] {
+            assert!(outgoing["definitions"][definition].is_object(), "{}", definition);
+        }
+        let report = serde_json::to_value(&schemas[2].1).unwrap();
+        assert_eq!(report["title"], Value::from("TickReport"));
+        assert!(report["properties"]["signature"].is_object());
+    }
+}
@@ -0,0 +1,191 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// First line of `TickReport::signed_payload`.
+pub const TICK_PAYLOAD_PREFIX: &str = "dews-tick-v2";
+
+/// Why a check failed, stored in `website_ticks.error_class`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "TEXT", rename_all = "snake_case"))]
+pub enum ErrorClass {
+    DnsFailure,
+    ConnectRefused,
+    TlsError,
+    Timeout,
+    /// The website answered, but not as expected, e.g. a non-2xx status.
+    AssertionFailed,
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::DnsFailure => "dns_failure",
+            ErrorClass::ConnectRefused => "connect_refused",
+            ErrorClass::TlsError => "tls_error",
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::AssertionFailed => "assertion_failed",
+        }
+    }
+}
+
+/// Timing breakdown as reported by a validator.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TickReportTimings {
+    pub dns_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub ttfb_ms: Option<f64>,
+}
+
+/// A check result signed by the validator that made it.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TickReport {
+    pub website_id: Uuid,
+    pub validator_id: Uuid,
+    /// Dispatch round the hub sent the check in; a validator reports once
+    /// per website and round.
+    pub round_id: Uuid,
+    pub status: String,
+    pub latency: f64,
+    /// When the check was made, in Unix milliseconds.
+    pub timestamp: i64,
+    /// Random string, never reused by the validator.
+    pub nonce: String,
+    pub http_status_code: Option<i32>,
+    pub error_class: Option<ErrorClass>,
+    pub response_size_bytes: Option<i64>,
+    #[serde(default)]
+    pub timings: TickReportTimings,
+    /// Base58 ed25519 signature over `TickReport::signed_payload`.
+    pub signature: String,
+}
+
+impl TickReport {
+    /// Bytes the validator signs: the prefix and every field in a fixed
+    /// order, one per line, with absent optional fields as empty lines.
+    /// Numbers are written as JavaScript's `String(number)` writes them, see
+    /// `js_number`.
+    pub fn signed_payload(&self) -> Vec<u8> {
+        fn optional<T: ToString>(value: Option<T>) -> String {
+            value.map(|value| value.to_string()).unwrap_or_default()
+        }
+        let optional_ms = |ms: Option<f64>| ms.map(js_number).unwrap_or_default();
+        [
+            TICK_PAYLOAD_PREFIX.to_string(),
+            self.validator_id.to_string(),
+            self.website_id.to_string(),
+            self.round_id.to_string(),
+            self.status.clone(),
+            js_number(self.latency),
+            self.timestamp.to_string(),
+            self.nonce.clone(),
+            optional(self.http_status_code),
+            optional(self.error_class.map(|class| class.as_str())),
+            optional(self.response_size_bytes),
+            optional_ms(self.timings.dns_ms),
+            optional_ms(self.timings.connect_ms),
+            optional_ms(self.timings.tls_ms),
+            optional_ms(self.timings.ttfb_ms),
+        ]
+        .join("\n")
+        .into_bytes()
+    }
+}
+
+/// Writes a finite number as JavaScript's `String(number)` does: the
+/// shortest digits that read back as the same number, in plain decimal
+/// between 1e-7 and 1e21 and in exponent form, e.g. `1.5e-7` or `1e+21`,
+/// outside it. `-0` is written `0`.
+fn js_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    // Rust's exponent form also has the shortest round-trip digits
+    let exponential = format!("{:e}", value.abs());
+    let (mantissa, exponent) = exponential.split_once('e').unwrap_or((&exponential, "0"));
+    let digits = mantissa.replace('.', "");
+    let exponent = exponent.parse::<i32>().unwrap_or(0);
+    let sign = if value < 0.0 { "-" } else { "" };
+    // The decimal point sits after `point` digits
+    let point = exponent + 1;
+    let len = digits.len() as i32;
+
+    let body = if len <= point && point <= 21 {
+        format!("{}{}", digits, "0".repeat((point - len) as usize))
+    } else if 0 < point && point <= 21 {
+        format!("{}.{}", &digits[..point as usize], &digits[point as usize..])
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let exponent = format!("{}{}", if exponent < 0 { "-" } else { "+" }, exponent.abs());
+        match digits.split_at(1) {
+            (first, "") => format!("{}e{}", first, exponent),
+            (first, rest) => format!("{}.{}e{}", first, rest, exponent),
+        }
+    };
+    format!("{}{}", sign, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_written_as_javascript_writes_them() {
+        // `[x, String(x)]` as printed by Node
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (120.0, "120"),
+            (182.5, "182.5"),
+            (0.1, "0.1"),
+            (-150.25, "-150.25"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (1.5e-7, "1.5e-7"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (12.000001, "12.000001"),
+            (33.333333333333336, "33.333333333333336"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(js_number(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn signed_payload_matches_the_validator() {
+        let report = TickReport {
+            website_id: Uuid::from_u128(0xaa),
+            validator_id: Uuid::from_u128(0xdd),
+            round_id: Uuid::from_u128(0xee),
+            status: "Bad".to_string(),
+            latency: 182.5,
+            timestamp: 1760000000000,
+            nonce: "c0ffee00c0ffee00c0ffee00".to_string(),
+            http_status_code: Some(503),
+            error_class: Some(ErrorClass::AssertionFailed),
+            response_size_bytes: Some(1024),
+            timings: TickReportTimings {
+                dns_ms: Some(3.0),
+                connect_ms: None,
+                tls_ms: Some(1.5e-7),
+                ttfb_ms: Some(150.25),
+            },
+            signature: String::new(),
+        };
+        // `canonicalReport` of `validator/index.ts` for the same report
+        let expected = "dews-tick-v2\n00000000-0000-0000-0000-0000000000dd\n00000000-0000-0000-0000-0000000000aa\n\
+                        00000000-0000-0000-0000-0000000000ee\nBad\n182.5\n1760000000000\nc0ffee00c0ffee00c0ffee00\n\
+                        503\nassertion_failed\n1024\n3\n\n1.5e-7\n150.25";
+        assert_eq!(String::from_utf8(report.signed_payload()).unwrap(), expected);
+    }
+}
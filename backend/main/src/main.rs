@@ -1,7 +1,23 @@
 use axum::{http, routing::get_service, Extension, Router};
-use api::routes::routes;
+use api::{
+    consensus::{run_consensus_job, validate_consensus_rule},
+    gateway::ValidatorGateway,
+    geoip::{resolve_validator_locations, run_geoip_resolver, GeoResolver},
+    heartbeat::run_heartbeat_evaluator,
+    incidents::run_incident_evaluator,
+    liveness::run_liveness_job,
+    payout_scheduler::run_payout_scheduler,
+    payouts::run_payout_reconciler,
+    reputation::run_reputation_job,
+    retention::run_retention_job,
+    scheduler::run_scheduler,
+    rollups::{backfill_rollups, run_rollup_job},
+    routes::routes,
+};
+use chrono::NaiveDate;
 use config::get_config;
 use db::connection::postgresDb;
+use protocol::schema::schemas;
 use sqlx::PgPool;
 use tower_http::cors::{CorsLayer, Any};
 use tower::ServiceBuilder;
@@ -11,10 +27,55 @@ use http::{Method, HeaderValue};
 #[tokio::main]
 async fn main() {
     let config = get_config();
+    if let Err(e) = config.validate().and_then(|()| validate_consensus_rule(&config)) {
+        panic!("Invalid configuration: {}", e);
+    }
+
+    // `main protocol-schema [dir]` writes the JSON Schemas of the hub/validator
+    // messages, then exits; it needs no database
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("protocol-schema") {
+        let dir = std::path::PathBuf::from(args.get(2).map(String::as_str).unwrap_or("schemas"));
+        std::fs::create_dir_all(&dir).expect("Failed to create schema directory");
+        for (name, schema) in schemas() {
+            let path = dir.join(format!("{}.schema.json", name));
+            let json = serde_json::to_string_pretty(&schema).expect("Failed to serialize schema");
+            std::fs::write(&path, json).expect("Failed to write schema");
+            println!("Wrote {}", path.display());
+        }
+        return;
+    }
 
     let db = postgresDb::new().await.expect("Failed to initialize db");
     let pool = db.get_postgres_connection_pool().unwrap();
 
+    // `main backfill-rollups <from> <to>` recomputes rollups for whole days, then exits
+    if args.get(1).map(String::as_str) == Some("backfill-rollups") {
+        let parse_day = |arg: Option<&String>| {
+            arg.and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+                .expect("usage: main backfill-rollups <YYYY-MM-DD> <YYYY-MM-DD>")
+        };
+        let from = parse_day(args.get(2));
+        let to = parse_day(args.get(3));
+        backfill_rollups(&pool, from, to)
+            .await
+            .expect("Failed to backfill rollups");
+        return;
+    }
+
+    // `main resolve-locations` re-resolves the location of every validator, then exits
+    if args.get(1).map(String::as_str) == Some("resolve-locations") {
+        let resolver = GeoResolver::from_config(&config)
+            .expect("Failed to open GeoIP database")
+            .expect("GEOIP_DATABASE is not set");
+        let located = resolve_validator_locations(&pool, &resolver, true)
+            .await
+            .expect("Failed to resolve validator locations");
+        println!("Located {} validators", located);
+        return;
+    }
+
     // Debug: List tables
     let rows: Vec<(String,)> = sqlx::query_as(
         "SELECT table_name FROM information_schema.tables WHERE table_schema = 'public'"
@@ -28,6 +89,21 @@ async fn main() {
         println!("{}", table_name);
     }
 
+    tokio::spawn(run_heartbeat_evaluator(pool.clone()));
+    tokio::spawn(run_rollup_job(pool.clone()));
+    tokio::spawn(run_retention_job(pool.clone()));
+    tokio::spawn(run_consensus_job(pool.clone()));
+    tokio::spawn(run_incident_evaluator(pool.clone()));
+    tokio::spawn(run_reputation_job(pool.clone()));
+    tokio::spawn(run_geoip_resolver(pool.clone()));
+    tokio::spawn(run_liveness_job(pool.clone()));
+    tokio::spawn(run_payout_reconciler(pool.clone()));
+    tokio::spawn(run_payout_scheduler(pool.clone()));
+
+    // Validators connected to `/api/validators/ws`; shared by the socket handlers and the scheduler
+    let gateway = ValidatorGateway::default();
+    tokio::spawn(run_scheduler(pool.clone(), gateway.clone()));
+
     // CORS Layer
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap()) // Change this as needed
@@ -41,6 +117,7 @@ async fn main() {
             ServiceBuilder::new()
                 .layer(cors)
                 .layer(Extension(pool))
+                .layer(Extension(gateway))
         );
 
     let listener = tokio::net::TcpListener::bind(&config.host_port)
@@ -133,12 +133,12 @@ function App() {
     return websites?.map(website => {
       // Sort ticks by creation time
       const sortedTicks = [...website.ticks].sort((a, b) => 
-        new Date(b.created_at).getTime() - new Date(a.created_at).getTime()
+        new Date(b.createdAt).getTime() - new Date(a.createdAt).getTime()
       );
 
       const thirtyMinutesAgo = new Date(Date.now() - 30 * 60 * 1000);
       const recentTicks = sortedTicks.filter(tick =>
-        new Date(tick.created_at) > thirtyMinutesAgo
+        new Date(tick.createdAt) > thirtyMinutesAgo
       );
       // Aggregate ticks into 3-minute windows (10 windows total)
       const windows: UptimeStatus[] = [];
@@ -148,7 +148,7 @@ function App() {
         const windowEnd = new Date(Date.now() - i * 3 * 60 * 1000);
         
         const windowTicks = recentTicks.filter(tick => {
-          const tickTime = new Date(tick.created_at);
+          const tickTime = new Date(tick.createdAt);
           return tickTime >= windowStart && tickTime < windowEnd;
         });
 
@@ -167,7 +167,7 @@ function App() {
 
       // Format the last checked time
       const lastChecked = sortedTicks[0]
-        ? new Date(sortedTicks[0].created_at).toLocaleTimeString()
+        ? new Date(sortedTicks[0].createdAt).toLocaleTimeString()
         : 'Never';
 
       return {
@@ -8,7 +8,7 @@ interface Website {
     url: string;
     ticks: {
         id: string;
-        created_at: string;
+        createdAt: string;
         status: string;
         latency: number;
     }[];
@@ -19,13 +19,25 @@ export function useWebsites() {
     const [websites, setWebsites] = useState<Website[]>([]);
 
     async function refreshWebsites() {    
-        const response = await axios.get(`${API_BACKEND_URL}/api/websites?user_id=`+userId, {
-            headers: {
-                Authorization: token,
-            },
-        });
-
-        setWebsites(response.data.data.websites);
+        const allWebsites: Website[] = [];
+        let cursor: string | null = null;
+
+        do {
+            const response: { data: { data: { websites: Website[], nextCursor: string | null } } } = await axios.get(`${API_BACKEND_URL}/api/websites`, {
+                params: {
+                    user_id: userId,
+                    cursor: cursor ?? undefined,
+                },
+                headers: {
+                    Authorization: token,
+                },
+            });
+
+            allWebsites.push(...response.data.data.websites);
+            cursor = response.data.data.nextCursor;
+        } while (cursor);
+
+        setWebsites(allWebsites);
     }
 
     useEffect(() => {
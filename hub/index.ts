@@ -1,5 +1,5 @@
 import { randomUUIDv7, type ServerWebSocket } from "bun";
-import type { IncomingMessage, SignupIncomingMessage } from "./types";
+import { CAPABILITIES, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, type Capability, type IncomingMessage, type SignupIncomingMessage } from "./types";
 import { PublicKey } from "@solana/web3.js";
 import nacl from "tweetnacl";
 import nacl_util from "tweetnacl-util";
@@ -8,10 +8,23 @@ import { Client } from "pg";
 const db = new Client({ connectionString: process.env.DATABASE_URL });
 await db.connect();
 
-const availableValidators: { validatorId: string, socket: ServerWebSocket<unknown>, publicKey: string }[] = [];
+const availableValidators: {
+    validatorId: string,
+    socket: ServerWebSocket<unknown>,
+    publicKey: string,
+    capabilities: Capability[],
+    // Checks sent that it has not answered yet
+    pending: number,
+}[] = [];
 
 const CALLBACKS: { [callbackId: string]: (data: IncomingMessage) => void } = {}
-const COST_PER_VALIDATION = 100; // in lamports
+// Challenge sent to each connection, signed into its signup
+const CHALLENGES = new Map<ServerWebSocket<unknown>, string>();
+const BACKEND_URL = process.env.BACKEND_URL ?? 'http://localhost:8000';
+// Rounds are planned by the backend's admin API
+const ADMIN_TOKEN = process.env.ADMIN_TOKEN ?? '';
+const SCHEDULER_POLL_SECS = Number(process.env.SCHEDULER_POLL_SECS ?? 5);
+const VALIDATOR_RESPONSE_TIMEOUT_SECS = Number(process.env.VALIDATOR_RESPONSE_TIMEOUT_SECS ?? 30);
 
 Bun.serve({
     fetch(req, server) {
@@ -22,13 +35,30 @@ Bun.serve({
     },
     port: 8081,
     websocket: {
+        open(ws: ServerWebSocket<unknown>) {
+            const challenge = crypto.randomUUID().replaceAll('-', '');
+            CHALLENGES.set(ws, challenge);
+            ws.send(JSON.stringify({ type: 'challenge', data: { challenge } }));
+        },
         async message(ws: ServerWebSocket<unknown>, message: string) {
             const data: IncomingMessage = JSON.parse(message);
 
             if (data.type === 'signup') {
                 console.log(data.data)
-                const verified = await verifyMessage(
-                    `Signed message for ${data.data.callbackId}, ${data.data.publicKey}`,
+                if (!(data.data.protocolVersion >= MIN_PROTOCOL_VERSION)) {
+                    ws.send(JSON.stringify({
+                        type: 'error',
+                        data: {
+                            callbackId: data.data.callbackId,
+                            message: `protocol version ${data.data.protocolVersion} is not supported, expected ${MIN_PROTOCOL_VERSION} to ${PROTOCOL_VERSION}`,
+                        },
+                    }));
+                    return;
+                }
+                const challenge = CHALLENGES.get(ws);
+                CHALLENGES.delete(ws);
+                const verified = challenge === data.data.challenge && await verifyMessage(
+                    `Signed message for ${data.data.callbackId}, ${data.data.publicKey}, ${challenge}`,
                     data.data.publicKey,
                     data.data.signedMessage
                 );
@@ -36,17 +66,28 @@ Bun.serve({
                     await signupHandler(ws, data.data);
                 }
             } else if (data.type === 'validate') {
-                CALLBACKS[data.data.callbackId](data);
+                const callback = CALLBACKS[data.data.callbackId];
+                if (!callback) return;
                 delete CALLBACKS[data.data.callbackId];
+                callback(data);
+            } else if (data.type === 'heartbeat') {
+                const validator = availableValidators.find(v => v.socket === ws);
+                if (validator) {
+                    await recordHeartbeat(validator.validatorId);
+                }
             }
         },
         async close(ws: ServerWebSocket<unknown>) {
-            availableValidators.splice(availableValidators.findIndex(v => v.socket === ws), 1);
+            CHALLENGES.delete(ws);
+            const index = availableValidators.findIndex(v => v.socket === ws);
+            if (index === -1) return;
+            const [validator] = availableValidators.splice(index, 1);
+            await db.query('UPDATE validators SET online = false WHERE id = $1', [validator.validatorId]);
         }
     },
 });
 
-async function signupHandler(ws: ServerWebSocket<unknown>, { ip, publicKey, signedMessage, callbackId }: SignupIncomingMessage) {
+async function signupHandler(ws: ServerWebSocket<unknown>, { ip, publicKey, signedMessage, callbackId, protocolVersion, capabilities }: SignupIncomingMessage) {
     console.log("signuphandler")
     const { rows: validatorRows } = await db.query(
         'SELECT * FROM validators WHERE public_key = $1 LIMIT 1',
@@ -56,20 +97,31 @@ async function signupHandler(ws: ServerWebSocket<unknown>, { ip, publicKey, sign
     let validatorId: string;
     if (validatorRows.length > 0) {
         const validator = validatorRows[0];
+        if (validator.status === 'banned') {
+            ws.send(JSON.stringify({ type: 'error', data: { callbackId, message: 'Validator is banned' } }));
+            ws.close();
+            return;
+        }
         validatorId = validator.id;
     } else {
+        // New validators wait for an admin to approve them, as in the backend
+        const status = process.env.VALIDATOR_AUTO_APPROVE === 'true' ? 'active' : 'pending';
         const result = await db.query(
-            'INSERT INTO validators (ip, public_key, location) VALUES ($1, $2, $3) RETURNING *',
-            [ip, publicKey, 'unknown']
+            "INSERT INTO validators (ip, public_key, location, status, status_changed_at) VALUES ($1, $2, $3, $4, (now() AT TIME ZONE 'utc')) RETURNING *",
+            [ip, publicKey, 'unknown', status]
         );
         validatorId = result.rows[0].id;
     }
 
+    // Speak the older of both versions and only the capabilities both sides know
+    const negotiated: Capability[] = CAPABILITIES.filter(capability => capabilities?.includes(capability));
     ws.send(JSON.stringify({
         type: 'signup',
         data: {
             validatorId,
             callbackId,
+            protocolVersion: Math.min(protocolVersion, PROTOCOL_VERSION),
+            capabilities: negotiated,
         },
     }));
 
@@ -77,7 +129,22 @@ async function signupHandler(ws: ServerWebSocket<unknown>, { ip, publicKey, sign
         validatorId,
         socket: ws,
         publicKey,
+        capabilities: negotiated,
+        pending: 0,
     });
+    await recordHeartbeat(validatorId);
+}
+
+// Same as `record_heartbeat` in the backend
+async function recordHeartbeat(validatorId: string) {
+    await db.query(
+        "UPDATE validators SET online = true, last_seen_at = (now() AT TIME ZONE 'utc') WHERE id = $1",
+        [validatorId]
+    );
+    await db.query(
+        "INSERT INTO validator_presence (validator_id, minute) VALUES ($1, date_trunc('minute', now() AT TIME ZONE 'utc')) ON CONFLICT (validator_id, minute) DO NOTHING",
+        [validatorId]
+    );
 }
 
 async function verifyMessage(message: string, publicKey: string, signature: string) {
@@ -90,26 +157,77 @@ async function verifyMessage(message: string, publicKey: string, signature: stri
     return result;
 }
 
+interface PlannedCheck {
+    websiteId: string;
+    url: string;
+    validatorIds: string[];
+}
+
+// Same as `plan_round` in the backend: due websites are assigned to
+// VALIDATORS_PER_CHECK of the validators offered and their assignments
+// recorded, so only those validators' reports are accepted for the round
+async function planRound(): Promise<{ roundId: string | null, checks: PlannedCheck[] }> {
+    const validators = availableValidators
+        .filter(validator => validator.capabilities.includes('http'))
+        .map(validator => ({ validator_id: validator.validatorId, load: validator.pending }));
+    if (validators.length === 0) {
+        return { roundId: null, checks: [] };
+    }
+
+    const response = await fetch(`${BACKEND_URL}/api/admin/schedule/round`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json', 'X-Admin-Token': ADMIN_TOKEN },
+        body: JSON.stringify({ validators }),
+    });
+    if (!response.ok) {
+        throw new Error(`planning round failed with ${response.status}: ${await response.text()}`);
+    }
+    const { data } = await response.json();
+    return data;
+}
+
 setInterval(async () => {
-    console.log("runnung",availableValidators);
-    const { rows: websitesToMonitor } = await db.query('SELECT * FROM websites WHERE disabled = false');
-    
-    for (const website of websitesToMonitor) {
-        availableValidators.forEach(validator => {
+    let round;
+    try {
+        round = await planRound();
+    } catch (err) {
+        console.error("Failed to plan round:", err);
+        return;
+    }
+    const { roundId, checks } = round;
+    if (!roundId) return;
+
+    for (const check of checks) {
+        for (const validatorId of check.validatorIds) {
+            const validator = availableValidators.find(v => v.validatorId === validatorId);
+            if (!validator) continue;
             const callbackId = randomUUIDv7();
-            console.log(`Sending validate to ${validator.validatorId} ${website.url}`);
+            console.log(`Sending validate to ${validator.validatorId} ${check.url}`);
 
             validator.socket.send(JSON.stringify({
                 type: 'validate',
                 data: {
-                    url: website.url,
-                    callbackId
+                    url: check.url,
+                    callbackId,
+                    websiteId: check.websiteId,
+                    roundId,
                 },
             }));
+            validator.pending += 1;
+
+            // An unanswered check stops counting towards the validator's load
+            const timeout = setTimeout(() => {
+                if (CALLBACKS[callbackId]) {
+                    delete CALLBACKS[callbackId];
+                    validator.pending -= 1;
+                }
+            }, VALIDATOR_RESPONSE_TIMEOUT_SECS * 1000);
 
             CALLBACKS[callbackId] = async (data: IncomingMessage) => {
+                clearTimeout(timeout);
+                validator.pending -= 1;
                 if (data.type === 'validate') {
-                    const { validatorId, status, latency, signedMessage } = data.data;
+                    const { validatorId, signedMessage } = data.data;
                     const verified = await verifyMessage(
                         `Replying to ${callbackId}`,
                         validator.publicKey,
@@ -117,24 +235,21 @@ setInterval(async () => {
                     );
                     if (!verified) return;
 
-                    const tx = await db.query('BEGIN');
+                    // The backend verifies the validator's signature over the report and records it
                     try {
-                        await db.query(`
-                           INSERT INTO website_ticks (website_id, validator_id, status, latency, created_at)
-                           VALUES ($1, $2, $3, $4, $5)
-                        `, [website.id, validatorId, status, latency, new Date()]);
-
-                        await db.query(`
-                            UPDATE validators SET pending_payouts = pending_payouts + $1 WHERE id = $2
-                        `, [COST_PER_VALIDATION, validatorId]);
-
-                        await db.query('COMMIT');
+                        const response = await fetch(`${BACKEND_URL}/api/ticks`, {
+                            method: 'POST',
+                            headers: { 'Content-Type': 'application/json' },
+                            body: JSON.stringify(data.data.report),
+                        });
+                        if (!response.ok) {
+                            console.error(`Tick from ${validatorId} rejected:`, await response.text());
+                        }
                     } catch (err) {
-                        await db.query('ROLLBACK');
-                        console.error("Transaction error:", err);
+                        console.error("Failed to submit tick:", err);
                     }
                 }
             };
-        });
+        }
     }
-}, 60 * 1000);
+}, SCHEDULER_POLL_SECS * 1000);
@@ -1,28 +1,72 @@
+/// Mirrors the `protocol` crate in the backend; `cargo run -p main -- protocol-schema`
+/// writes the JSON Schemas of these messages
+export const PROTOCOL_VERSION = 2;
+export const MIN_PROTOCOL_VERSION = 2;
+export const CAPABILITIES = ['http', 'timings'] as const;
+export type Capability = typeof CAPABILITIES[number];
+
 export interface SignupIncomingMessage {
+    protocolVersion: number;
+    capabilities?: string[];
     ip: string;
     publicKey: string;
     signedMessage: string;
     callbackId: string;
+    challenge: string;
+}
+
+export const ERROR_CLASSES = ['dns_failure', 'connect_refused', 'tls_error', 'timeout', 'assertion_failed'] as const;
+export type ErrorClass = typeof ERROR_CLASSES[number];
+
+/// Check result signed by the validator, forwarded as is to `POST /api/ticks`
+export interface TickReport {
+    websiteId: string;
+    validatorId: string;
+    roundId: string;
+    status: 'Good' | 'Bad';
+    latency: number;
+    timestamp: number;
+    nonce: string;
+    httpStatusCode: number | null;
+    errorClass: ErrorClass | null;
+    responseSizeBytes: number | null;
+    timings: {
+        dnsMs: number | null;
+        connectMs: number | null;
+        tlsMs: number | null;
+        ttfbMs: number | null;
+    };
+    signature: string;
 }
 
 export interface ValidateIncomingMessage {
     callbackId: string;
     signedMessage: string;
-    status: 'Good' | 'Bad';
-    latency: number;
-    websiteId: string;
     validatorId: string;
+    report: TickReport;
+}
+
+export interface ChallengeOutgoingMessage {
+    challenge: string;
 }
 
 export interface SignupOutgoingMessage {
     validatorId: string;
     callbackId: string;
+    protocolVersion: number;
+    capabilities: Capability[];
 }
 
 export interface ValidateOutgoingMessage {
     url: string,
     callbackId: string,
     websiteId: string;
+    roundId: string;
+}
+
+export interface ErrorOutgoingMessage {
+    callbackId: string | null;
+    message: string;
 }
 
 export type IncomingMessage = {
@@ -31,12 +75,20 @@ export type IncomingMessage = {
 } | {
     type: 'validate'
     data: ValidateIncomingMessage
+} | {
+    type: 'heartbeat'
 }
 
 export type OutgoingMessage = {
+    type: 'challenge'
+    data: ChallengeOutgoingMessage
+} | {
     type: 'signup'
     data: SignupOutgoingMessage
 } | {
     type: 'validate'
     data: ValidateOutgoingMessage
+} | {
+    type: 'error'
+    data: ErrorOutgoingMessage
 }
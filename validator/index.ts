@@ -1,5 +1,5 @@
 import { randomUUIDv7 } from "bun";
-import type { OutgoingMessage, SignupOutgoingMessage, ValidateOutgoingMessage } from "./types";
+import { CAPABILITIES, PROTOCOL_VERSION, type ErrorClass, type OutgoingMessage, type SignupOutgoingMessage, type TickReport, type ValidateOutgoingMessage } from "./types";
 import { Keypair } from "@solana/web3.js";
 import nacl from "tweetnacl";
 import nacl_util from "tweetnacl-util";
@@ -14,75 +14,149 @@ async function main() {
     const keypair = Keypair.fromSecretKey(
         Uint8Array.from(secretKey64)
     );
-    const ws = new WebSocket("ws://localhost:8081");
+    // The hub, or the backend's gateway at ws://localhost:8000/api/validators/ws
+    const ws = new WebSocket(process.env.HUB_URL ?? "ws://localhost:8081");
+
+    // Keeps the validator marked online once it has signed up
+    setInterval(() => {
+        if (validatorId && ws.readyState === WebSocket.OPEN) {
+            ws.send(JSON.stringify({ type: 'heartbeat' }));
+        }
+    }, HEARTBEAT_INTERVAL_MS);
 
     ws.onmessage = async (event) => {
         const data: OutgoingMessage = JSON.parse(event.data);
         if (data.type === 'signup') {
             CALLBACKS[data.data.callbackId]?.(data.data)
             delete CALLBACKS[data.data.callbackId];
+        } else if (data.type === 'challenge') {
+            await signup(ws, data.data.challenge, keypair);
         } else if (data.type === 'validate') {
             await validateHandler(ws, data.data, keypair);
+        } else if (data.type === 'error') {
+            console.error("Hub rejected message:", data.data.message);
         }
     }
 
-    ws.onopen = async () => {
-        const callbackId = randomUUIDv7();
-        CALLBACKS[callbackId] = (data: SignupOutgoingMessage) => {
-            validatorId = data.validatorId;
-        }
-        const signedMessage = await signMessage(`Signed message for ${callbackId}, ${keypair.publicKey}`, keypair);
-
-        ws.send(JSON.stringify({
-            type: 'signup',
-            data: {
-                callbackId,
-                ip: '127.0.0.1',
-                publicKey: keypair.publicKey,
-                signedMessage,
-            },
-        }));
+}
+
+// Answers the challenge sent when the connection opens
+async function signup(ws: WebSocket, challenge: string, keypair: Keypair) {
+    const callbackId = randomUUIDv7();
+    CALLBACKS[callbackId] = (data: SignupOutgoingMessage) => {
+        validatorId = data.validatorId;
     }
+    const signedMessage = await signMessage(`Signed message for ${callbackId}, ${keypair.publicKey}, ${challenge}`, keypair);
+
+    ws.send(JSON.stringify({
+        type: 'signup',
+        data: {
+            protocolVersion: PROTOCOL_VERSION,
+            capabilities: CAPABILITIES,
+            callbackId,
+            challenge,
+            ip: '127.0.0.1',
+            publicKey: keypair.publicKey,
+            signedMessage,
+        },
+    }));
+}
+
+const CHECK_TIMEOUT_MS = 10_000;
+const HEARTBEAT_INTERVAL_MS = 30_000;
+
+// Maps a failed fetch to one of the error classes the backend accepts
+function classifyError(error: any): ErrorClass | null {
+    const code = String(error?.code ?? '');
+    const name = String(error?.name ?? '');
+    if (name === 'TimeoutError' || name === 'AbortError' || code === 'ETIMEDOUT') return 'timeout';
+    if (code === 'ENOTFOUND' || code === 'EAI_AGAIN' || code.startsWith('DNS')) return 'dns_failure';
+    if (code === 'ConnectionRefused' || code === 'ECONNREFUSED' || code === 'FailedToOpenSocket') return 'connect_refused';
+    if (code.includes('CERT') || code.includes('TLS') || code.includes('SSL')) return 'tls_error';
+    return null;
 }
 
-async function validateHandler(ws: WebSocket, { url, callbackId, websiteId }: ValidateOutgoingMessage, keypair: Keypair) {
+async function validateHandler(ws: WebSocket, { url, callbackId, websiteId, roundId }: ValidateOutgoingMessage, keypair: Keypair) {
     console.log(`Validating ${url}`);
     const startTime = Date.now();
     const signature = await signMessage(`Replying to ${callbackId}`, keypair);
 
+    let result: Omit<TickReport, 'websiteId' | 'validatorId' | 'roundId' | 'timestamp' | 'nonce' | 'signature'>;
     try {
-        const response = await fetch(url);
+        const response = await fetch(url, { signal: AbortSignal.timeout(CHECK_TIMEOUT_MS) });
+        // fetch resolves once the headers are in; DNS, connect and TLS times are not exposed
+        const ttfbMs = Date.now() - startTime;
+        const body = await response.arrayBuffer();
         const endTime = Date.now();
         const latency = endTime - startTime;
         const status = response.status;
 
         console.log(url);
         console.log(status);
-        ws.send(JSON.stringify({
-            type: 'validate',
-            data: {
-                callbackId,
-                status: status === 200 ? 'Good' : 'Bad',
-                latency,
-                websiteId,
-                validatorId,
-                signedMessage: signature,
-            },
-        }));
+        result = {
+            status: status === 200 ? 'Good' : 'Bad',
+            latency,
+            httpStatusCode: status,
+            errorClass: status === 200 ? null : 'assertion_failed',
+            responseSizeBytes: body.byteLength,
+            timings: { dnsMs: null, connectMs: null, tlsMs: null, ttfbMs },
+        };
     } catch (error) {
-        ws.send(JSON.stringify({
-            type: 'validate',
-            data: {
-                callbackId,
-                status:'Bad',
-                latency: 1000,
-                websiteId,
-                validatorId,
-                signedMessage: signature,
-            },
-        }));
+        result = {
+            status: 'Bad',
+            latency: 1000,
+            httpStatusCode: null,
+            errorClass: classifyError(error),
+            responseSizeBytes: null,
+            timings: { dnsMs: null, connectMs: null, tlsMs: null, ttfbMs: null },
+        };
         console.error(error);
     }
+
+    const report = {
+        websiteId,
+        validatorId: validatorId!,
+        roundId,
+        timestamp: Date.now(),
+        nonce: Buffer.from(crypto.getRandomValues(new Uint8Array(16))).toString('hex'),
+        ...result,
+    };
+    ws.send(JSON.stringify({
+        type: 'validate',
+        data: {
+            callbackId,
+            validatorId,
+            signedMessage: signature,
+            report: { ...report, signature: signReport(report, keypair) },
+        },
+    }));
+}
+
+// Must match `TickReport::signed_payload` in the backend
+function canonicalReport(report: Omit<TickReport, 'signature'>) {
+    const optional = (value: number | string | null) => value === null ? '' : String(value);
+    return [
+        'dews-tick-v2',
+        report.validatorId,
+        report.websiteId,
+        report.roundId,
+        report.status,
+        String(report.latency),
+        String(report.timestamp),
+        report.nonce,
+        optional(report.httpStatusCode),
+        optional(report.errorClass),
+        optional(report.responseSizeBytes),
+        optional(report.timings.dnsMs),
+        optional(report.timings.connectMs),
+        optional(report.timings.tlsMs),
+        optional(report.timings.ttfbMs),
+    ].join('\n');
+}
+
+function signReport(report: Omit<TickReport, 'signature'>, keypair: Keypair) {
+    const signature = nacl.sign.detached(nacl_util.decodeUTF8(canonicalReport(report)), keypair.secretKey);
+    return bs58.encode(signature);
 }
 
 async function signMessage(message: string, keypair: Keypair) {
@@ -92,8 +166,4 @@ async function signMessage(message: string, keypair: Keypair) {
     return JSON.stringify(Array.from(signature));
 }
 
-main();
-
-setInterval(async () => {
-
-}, 10000);
\ No newline at end of file
+main();
\ No newline at end of file
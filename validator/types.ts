@@ -1,19 +1,66 @@
+/// Mirrors the `protocol` crate in the backend; `cargo run -p main -- protocol-schema`
+/// writes the JSON Schemas of these messages
+export const PROTOCOL_VERSION = 2;
+export const CAPABILITIES = ['http', 'timings'] as const;
+export type Capability = typeof CAPABILITIES[number];
+
+export type ErrorClass = 'dns_failure' | 'connect_refused' | 'tls_error' | 'timeout' | 'assertion_failed';
+
+/// Check result, signed over its canonical form and sent to the hub
+export interface TickReport {
+    websiteId: string;
+    validatorId: string;
+    roundId: string;
+    status: 'Good' | 'Bad';
+    latency: number;
+    timestamp: number;
+    nonce: string;
+    httpStatusCode: number | null;
+    errorClass: ErrorClass | null;
+    responseSizeBytes: number | null;
+    timings: {
+        dnsMs: number | null;
+        connectMs: number | null;
+        tlsMs: number | null;
+        ttfbMs: number | null;
+    };
+    signature: string;
+}
+
+export interface ChallengeOutgoingMessage {
+    challenge: string;
+}
+
 export interface SignupOutgoingMessage {
     validatorId: string;
     callbackId: string;
+    protocolVersion: number;
+    capabilities: Capability[];
 }
 
 export interface ValidateOutgoingMessage {
     url: string,
     callbackId: string,
     websiteId: string;
+    roundId: string;
+}
+
+export interface ErrorOutgoingMessage {
+    callbackId: string | null;
+    message: string;
 }
 
 
 export type OutgoingMessage = {
+    type: 'challenge'
+    data: ChallengeOutgoingMessage
+} | {
     type: 'signup'
     data: SignupOutgoingMessage
 } | {
     type: 'validate'
     data: ValidateOutgoingMessage
+} | {
+    type: 'error'
+    data: ErrorOutgoingMessage
 }
\ No newline at end of file